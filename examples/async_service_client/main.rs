@@ -86,7 +86,7 @@ fn main() {
           println!("Stopping");
         }
         _tick = tick_stream.select_next_some() => {
-          let service_is_ready = client.wait_for_service(&node).map(|_| true)
+          let service_is_ready = client.wait_for_service(&node).map(|r| r.is_ok())
               .or(async {
                 smol::Timer::after(Duration::from_secs(1));
                 false
@@ -56,7 +56,10 @@ fn main() {
     let mut sub_count = 0;
     loop {
       println!("Waiting for subscribers to appear...");
-      chatter_publisher.wait_for_subscription(&node).await;
+      chatter_publisher
+        .wait_for_subscription(&node)
+        .await
+        .unwrap();
       loop {
         count += 1;
         let message = format!("count={} {}", count, filler);
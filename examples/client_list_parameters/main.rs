@@ -1,14 +1,10 @@
 use std::{env, time::Duration};
 
-use futures::TryFutureExt;
 use smol::future::FutureExt;
 use ros2_client::{
-  rcl_interfaces::{ListParametersRequest, ListParametersResponse},
-  ros2::WriteError,
-  service::CallServiceError,
-  AService, Context, Name, Node, NodeName, NodeOptions, ServiceMapping, ServiceTypeName,
+  rcl_interfaces::ListParametersRequest, ros2::WriteError, Context, Node, NodeName, NodeOptions,
+  ServiceMapping,
 };
-use rustdds::{policy, QosPolicies, QosPolicyBuilder};
 
 fn main() {
   log4rs::init_file("log4rs.yaml", Default::default()).unwrap();
@@ -26,70 +22,52 @@ fn main() {
   // E.g. waiting for server does not work without this.
   smol::spawn(node.spinner().unwrap().spin()).detach();
 
-  let service_qos = create_qos();
-
   println!(">>> ros2_service node started");
 
-  let target_node = &args[1];
-  println!(">>> target node is '{target_node}'");
-  let service_name = Name::new(target_node, "list_parameters").unwrap();
-  println!(">>> connecting service {service_name:?}");
-
-  let client = node
-    .create_client::<AService<ListParametersRequest, ListParametersResponse>>(
-      ServiceMapping::Enhanced,
-      &service_name,
-      &ServiceTypeName::new("rcl_interfaces", "ListParameters"),
-      service_qos.clone(),
-      service_qos,
-    )
-    .unwrap();
+  let (namespace, base_name) = args[1].rsplit_once('/').unwrap_or(("", &args[1]));
+  let namespace = if namespace.is_empty() { "/" } else { namespace };
+  let target_node = match NodeName::new(namespace, base_name) {
+    Ok(name) => name,
+    Err(e) => {
+      println!("Bad node name {}: {e}", args[1]);
+      return;
+    }
+  };
+  println!(
+    ">>> target node is '{}'",
+    target_node.fully_qualified_name()
+  );
 
-  let request = ListParametersRequest {
-    depth: 0,
-    prefixes: vec![],
+  let client = match node.create_parameter_client(&target_node, ServiceMapping::Enhanced) {
+    Ok(client) => client,
+    Err(e) => {
+      println!("Cannot create parameter client: {e}");
+      return;
+    }
   };
 
   smol::block_on(async {
-    println!(">>> Waiting for ListParameters server to appear.");
-    client.wait_for_service(&node).await;
-    println!(">>> Connected to ListParameters server.");
+    println!(">>> Waiting for Parameter services to appear.");
+    client.wait_for_service(&node).await.unwrap();
+    println!(">>> Connected to Parameter services.");
 
-    match client.async_send_request(request).await {
-      Ok(req_id) => {
-        println!(">>> request sent {req_id:?}");
-        let response_or_timeout = client
-          .async_receive_response(req_id)
-          .map_err(CallServiceError::<()>::from)
-          .or(async {
-            smol::Timer::after(Duration::from_secs(15)).await;
-            println!(">>> Response timeout!!");
-            Err(WriteError::WouldBlock { data: () }.into())
-          });
-        match response_or_timeout.await {
-          Ok(response) => {
-            println!("<<< response: {:?}", response);
-          }
-          Err(e) => println!("<<< response error {:?}", e),
-        }
+    let response_or_timeout = client
+      .list(&[], ListParametersRequest::DEPTH_RECURSIVE)
+      .or(async {
+        smol::Timer::after(Duration::from_secs(15)).await;
+        println!(">>> Response timeout!!");
+        Err(WriteError::WouldBlock { data: () }.into())
+      });
+    match response_or_timeout.await {
+      Ok(result) => {
+        println!("<<< parameter names: {:?}", result.names);
+        println!("<<< prefixes: {:?}", result.prefixes);
       }
-      Err(e) => println!(">>> request sending error {e:?}"),
+      Err(e) => println!("<<< response error {:?}", e),
     }
   });
 }
 
-fn create_qos() -> QosPolicies {
-  let service_qos: QosPolicies = {
-    QosPolicyBuilder::new()
-      .reliability(policy::Reliability::Reliable {
-        max_blocking_time: rustdds::Duration::from_millis(100),
-      })
-      .history(policy::History::KeepLast { depth: 1 })
-      .build()
-  };
-  service_qos
-}
-
 fn create_node() -> Node {
   let context = Context::new().unwrap();
   context
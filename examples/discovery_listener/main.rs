@@ -1,20 +1,24 @@
 use futures::StreamExt;
-use ros2_client::{Context, NodeName, NodeOptions};
+use ros2_client::{Context, ContextOptions, NodeName, NodeOptions};
 
 pub fn main() {
-  let context = Context::new().unwrap();
+  // Passive observer, so that others do not see us in their ROS graph.
+  let context = Context::with_options(ContextOptions::new().passive_observer(true)).unwrap();
   let mut node = context
     .new_node(
       NodeName::new("/rustdds", "discovery_listener").unwrap(),
-      NodeOptions::default(),
+      NodeOptions::new().enable_rosout(false),
     )
     .unwrap();
 
   smol::spawn(node.spinner().unwrap().spin()).detach();
 
-  let status_event_stream = node.status_receiver().for_each(|event| async move {
-    println!("{:?}", event);
-  });
+  let status_event_stream = node
+    .status_receiver()
+    .unwrap()
+    .for_each(|event| async move {
+      println!("{:?}", event);
+    });
 
   smol::block_on(status_event_stream);
 }
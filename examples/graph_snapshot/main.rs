@@ -0,0 +1,44 @@
+//! Print a snapshot of the ROS 2 graph, like `rqt_graph` without a GUI.
+//!
+//! Usage: `graph_snapshot [--dot] [seconds]`
+//!
+//! Output is JSON, or Graphviz `dot` with `--dot`. Discovery runs for the given
+//! number of seconds (default 3) before taking the snapshot. To render an image:
+//! `graph_snapshot --dot | dot -Tpng -o graph.png`
+use std::time::Duration;
+
+use ros2_client::{Context, ContextOptions, NodeName, NodeOptions};
+
+pub fn main() {
+  let mut dot = false;
+  let mut seconds = 3;
+  for arg in std::env::args().skip(1) {
+    match arg.as_str() {
+      "--dot" => dot = true,
+      s => {
+        seconds = s
+          .parse()
+          .expect("Argument should be --dot or number of seconds")
+      }
+    }
+  }
+
+  // Passive observer, so that others do not see us in their ROS graph.
+  let context = Context::with_options(ContextOptions::new().passive_observer(true)).unwrap();
+  let mut node = context
+    .new_node(
+      NodeName::new("/rustdds", "graph_snapshot").unwrap(),
+      NodeOptions::new().enable_rosout(false),
+    )
+    .unwrap();
+
+  smol::spawn(node.spinner().unwrap().spin()).detach();
+  smol::block_on(smol::Timer::after(Duration::from_secs(seconds)));
+
+  let snapshot = node.graph_snapshot();
+  if dot {
+    print!("{}", snapshot.to_dot());
+  } else {
+    println!("{}", serde_json::to_string_pretty(&snapshot).unwrap());
+  }
+}
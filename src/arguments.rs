@@ -0,0 +1,365 @@
+//! Parsing of ROS 2 command line arguments, i.e. the `--ros-args` section.
+//!
+//! See [ROS command line arguments](https://design.ros2.org/articles/ros_command_line_arguments.html)
+//! in the ROS 2 design documents.
+//!
+//! Supported arguments are
+//! * `-r` / `--remap` : `[node_name:]from:=to` name remapping rule. Special
+//!   names `__node` and `__ns` rename the Node and its namespace.
+//! * `-p` / `--param` : `[node_name:]name:=value` parameter assignment
+//! * `--params-file` : path to a parameter file
+//! * `--log-level` : `[logger_name:=]level` rosout logging level
+//! * `--enable-rosout-logs` / `--disable-rosout-logs`
+//!
+//! Other standard ROS arguments are accepted, but ignored.
+use std::{fmt, path::PathBuf};
+
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use crate::{
+  log::LogLevel,
+  names::{NameError, NodeName},
+  parameters::{parse_parameter_value, Parameter},
+};
+
+/// Name remapping rule `from:=to`, possibly restricted to a single Node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemapRule {
+  node: Option<String>,
+  from: String,
+  to: String,
+}
+
+impl RemapRule {
+  /// Create a remapping rule. If `node` is given, then the rule only applies to
+  /// a Node with that (base) name.
+  pub fn new(node: Option<&str>, from: &str, to: &str) -> RemapRule {
+    RemapRule {
+      node: node.map(str::to_owned),
+      from: from.to_owned(),
+      to: to.to_owned(),
+    }
+  }
+
+  pub fn node(&self) -> Option<&str> {
+    self.node.as_deref()
+  }
+
+  pub fn from(&self) -> &str {
+    &self.from
+  }
+
+  pub fn to(&self) -> &str {
+    &self.to
+  }
+
+  /// Does this rule apply to the given Node? The name must be the original
+  /// name, i.e. before any `__node` or `__ns` remapping.
+  pub fn applies_to(&self, node_name: &NodeName) -> bool {
+    match self.node {
+      None => true,
+      Some(ref n) => n == node_name.base_name() || *n == node_name.fully_qualified_name(),
+    }
+  }
+}
+
+/// Parameter value given on the command line, possibly restricted to a single
+/// Node.
+#[derive(Debug, Clone)]
+pub struct ParameterOverride {
+  node: Option<String>,
+  parameter: Parameter,
+}
+
+impl ParameterOverride {
+  pub fn node(&self) -> Option<&str> {
+    self.node.as_deref()
+  }
+
+  pub fn parameter(&self) -> &Parameter {
+    &self.parameter
+  }
+}
+
+/// What went wrong in parsing command line arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentError {
+  /// Argument flag is not known.
+  Unknown(String),
+  /// Argument flag was not followed by a value
+  MissingValue(String),
+  /// Argument value is malformed: (flag, value)
+  BadValue(String, String),
+}
+
+impl fmt::Display for ArgumentError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ArgumentError::Unknown(a) => write!(f, "Unknown ROS argument {a:?}"),
+      ArgumentError::MissingValue(a) => write!(f, "ROS argument {a:?} requires a value"),
+      ArgumentError::BadValue(a, v) => write!(f, "Bad value for ROS argument {a}: {v:?}"),
+    }
+  }
+}
+
+impl std::error::Error for ArgumentError {}
+
+/// Parsed contents of `--ros-args` sections of a command line.
+#[derive(Debug, Clone, Default)]
+pub struct RosArguments {
+  remap_rules: Vec<RemapRule>,
+  parameter_overrides: Vec<ParameterOverride>,
+  params_files: Vec<PathBuf>,
+  log_levels: Vec<(Option<String>, LogLevel)>,
+  enable_rosout: Option<bool>,
+}
+
+impl RosArguments {
+  /// Parse a command line. Only arguments between `--ros-args` and `--` (or
+  /// the end of the command line) are considered, others are skipped.
+  ///
+  /// There may be several `--ros-args` sections.
+  pub fn parse<I, S>(args: I) -> Result<RosArguments, ArgumentError>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let mut result = RosArguments::default();
+    let mut in_ros_args = false;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      let arg = arg.as_ref();
+      if !in_ros_args {
+        in_ros_args = arg == "--ros-args";
+        continue;
+      }
+
+      let mut value_of = |flag: &str| {
+        args
+          .next()
+          .map(|v| v.as_ref().to_owned())
+          .ok_or_else(|| ArgumentError::MissingValue(flag.to_owned()))
+      };
+
+      match arg {
+        "--" => in_ros_args = false,
+        "--ros-args" => { /* already there */ }
+        "-r" | "--remap" => {
+          let value = value_of(arg)?;
+          let (node, from, to) = split_rule(&value)
+            .ok_or_else(|| ArgumentError::BadValue(arg.to_owned(), value.clone()))?;
+          result.remap_rules.push(RemapRule::new(node, from, to));
+        }
+        "-p" | "--param" => {
+          let value = value_of(arg)?;
+          let (node, name, param_value) = split_rule(&value)
+            .ok_or_else(|| ArgumentError::BadValue(arg.to_owned(), value.clone()))?;
+          let param_value = parse_parameter_value(param_value)
+            .map_err(|_| ArgumentError::BadValue(arg.to_owned(), value.clone()))?;
+          result.parameter_overrides.push(ParameterOverride {
+            node: node.map(str::to_owned),
+            parameter: Parameter {
+              name: name.to_owned(),
+              value: param_value,
+            },
+          });
+        }
+        "--params-file" => {
+          result.params_files.push(PathBuf::from(value_of(arg)?));
+        }
+        "--log-level" => {
+          let value = value_of(arg)?;
+          let (logger, level) = match value.split_once(":=") {
+            Some((logger, level)) => (Some(logger.to_owned()), level),
+            None => (None, value.as_str()),
+          };
+          let level = parse_log_level(level)
+            .ok_or_else(|| ArgumentError::BadValue(arg.to_owned(), value.clone()))?;
+          result.log_levels.push((logger, level));
+        }
+        "--enable-rosout-logs" => result.enable_rosout = Some(true),
+        "--disable-rosout-logs" => result.enable_rosout = Some(false),
+
+        // Known, but not supported. These take a value.
+        "-e" | "--enclave" | "--log-config-file" | "--log-file-name" => {
+          let value = value_of(arg)?;
+          info!("Ignoring unsupported ROS argument {arg} {value}");
+        }
+        // Known, but not supported. No value.
+        "--enable-stdout-logs"
+        | "--disable-stdout-logs"
+        | "--enable-external-lib-logs"
+        | "--disable-external-lib-logs" => {
+          info!("Ignoring unsupported ROS argument {arg}");
+        }
+        other => return Err(ArgumentError::Unknown(other.to_owned())),
+      }
+    }
+    Ok(result)
+  }
+
+  /// Parse the command line of this process, i.e. [`std::env::args()`].
+  pub fn from_env() -> Result<RosArguments, ArgumentError> {
+    Self::parse(std::env::args())
+  }
+
+  /// Combine node-local arguments (`self`) with process-wide arguments.
+  ///
+  /// Local remapping rules are tried first, and local parameter values
+  /// override global ones.
+  pub fn with_global(mut self, global: RosArguments) -> RosArguments {
+    self.remap_rules.extend(global.remap_rules);
+    let mut parameter_overrides = global.parameter_overrides;
+    parameter_overrides.append(&mut self.parameter_overrides);
+    self.parameter_overrides = parameter_overrides;
+    let mut params_files = global.params_files;
+    params_files.append(&mut self.params_files);
+    self.params_files = params_files;
+    let mut log_levels = global.log_levels;
+    log_levels.append(&mut self.log_levels);
+    self.log_levels = log_levels;
+    self.enable_rosout = self.enable_rosout.or(global.enable_rosout);
+    self
+  }
+
+  pub fn remap_rules(&self) -> &[RemapRule] {
+    &self.remap_rules
+  }
+
+  pub fn parameter_overrides(&self) -> &[ParameterOverride] {
+    &self.parameter_overrides
+  }
+
+  pub fn params_files(&self) -> &[PathBuf] {
+    &self.params_files
+  }
+
+  /// Apply `__node` and `__ns` remapping rules.
+  ///
+  /// The first applicable rule of each kind wins.
+  pub fn resolve_node_name(&self, node_name: &NodeName) -> Result<NodeName, NameError> {
+    let find = |special: &str| {
+      self
+        .remap_rules
+        .iter()
+        .find(|r| r.from == special && r.applies_to(node_name))
+        .map(|r| r.to.as_str())
+    };
+    let base_name = find("__node").unwrap_or(node_name.base_name());
+    let namespace = find("__ns").unwrap_or(node_name.namespace());
+    NodeName::new(namespace, base_name)
+  }
+
+  /// Parameter values applicable to the given Node, in order of increasing
+  /// precedence.
+  pub fn parameters_for(&self, node_name: &NodeName) -> Vec<Parameter> {
+    self
+      .parameter_overrides
+      .iter()
+      .filter(|p| match p.node {
+        None => true,
+        Some(ref n) => n == node_name.base_name() || *n == node_name.fully_qualified_name(),
+      })
+      .map(|p| p.parameter.clone())
+      .collect()
+  }
+
+  /// Logging level for the given Node, if any was specified.
+  ///
+  /// A logger-specific level takes precedence over the default level.
+  pub fn log_level_for(&self, node_name: &NodeName) -> Option<LogLevel> {
+    let specific = self.log_levels.iter().rev().find(|(logger, _)| {
+      logger.as_deref() == Some(node_name.base_name())
+        || logger.as_deref() == Some(&node_name.fully_qualified_name())
+    });
+    let default = self
+      .log_levels
+      .iter()
+      .rev()
+      .find(|(logger, _)| logger.is_none());
+    specific.or(default).map(|(_, level)| *level)
+  }
+
+  pub fn enable_rosout(&self) -> Option<bool> {
+    self.enable_rosout
+  }
+}
+
+// Split "[node:]left:=right" into its parts.
+fn split_rule(rule: &str) -> Option<(Option<&str>, &str, &str)> {
+  let (left, right) = rule.split_once(":=")?;
+  let (node, left) = match left.split_once(':') {
+    Some((node, left)) => (Some(node), left),
+    None => (None, left),
+  };
+  if left.is_empty() || node == Some("") {
+    None
+  } else {
+    Some((node, left, right))
+  }
+}
+
+fn parse_log_level(s: &str) -> Option<LogLevel> {
+  match s.to_ascii_lowercase().as_str() {
+    "debug" => Some(LogLevel::Debug),
+    "info" => Some(LogLevel::Info),
+    "warn" | "warning" => Some(LogLevel::Warn),
+    "error" => Some(LogLevel::Error),
+    "fatal" => Some(LogLevel::Fatal),
+    _ => None,
+  }
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_parse_ros_args() {
+  let args = RosArguments::parse([
+    "my_program",
+    "--foo",
+    "-p",
+    "--ros-args",
+    "-r",
+    "__node:=renamed",
+    "-r",
+    "__ns:=/space",
+    "--remap",
+    "other_node:chatter:=talk",
+    "-p",
+    "speed:=2.5",
+    "--params-file",
+    "f.yaml",
+    "--log-level",
+    "debug",
+    "--",
+    "-p",
+  ])
+  .unwrap();
+
+  assert_eq!(args.remap_rules().len(), 3);
+  assert_eq!(
+    args.remap_rules()[2],
+    RemapRule::new(Some("other_node"), "chatter", "talk")
+  );
+  assert_eq!(args.params_files(), &[PathBuf::from("f.yaml")]);
+
+  let node_name = NodeName::new("/", "my_node").unwrap();
+  assert_eq!(
+    args.resolve_node_name(&node_name).unwrap(),
+    NodeName::new("/space", "renamed").unwrap()
+  );
+  assert_eq!(args.log_level_for(&node_name), Some(LogLevel::Debug));
+  let params = args.parameters_for(&node_name);
+  assert_eq!(params.len(), 1);
+  assert!(matches!(params[0].value, crate::ParameterValue::Double(d) if d == 2.5));
+
+  assert_eq!(
+    RosArguments::parse(["--ros-args", "-r"]).unwrap_err(),
+    ArgumentError::MissingValue("-r".to_owned())
+  );
+  assert!(RosArguments::parse(["--ros-args", "--bogus"]).is_err());
+  assert!(RosArguments::parse(["--ros-args", "-r", "nothing"]).is_err());
+}
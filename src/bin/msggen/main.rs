@@ -1,13 +1,9 @@
 use std::{collections::BTreeMap, fs, io, io::Write};
 
 use clap::{Arg, Command}; // command line argument processing
-
-mod parser;
-mod stringparser;
+use ros2_client::dynamic::parser::{self, ArraySpecifier, BaseTypeName, Comment, Item, TypeName, Value};
 //mod gen;
 
-use parser::{ArraySpecifier, BaseTypeName, Comment, Item, TypeName, Value};
-
 fn main() -> io::Result<()> {
   //println!("msggen");
 
@@ -48,8 +44,7 @@ fn main() -> io::Result<()> {
 
     let type_name = std::path::Path::new(input_file_name)
       .file_stem()
-      .ok_or(io::Error::new(
-        io::ErrorKind::Other,
+      .ok_or(io::Error::other(
         "Input file did not have base name?",
       ))?
       .to_string_lossy()
@@ -71,11 +66,10 @@ fn main() -> io::Result<()> {
   } else if let Some(ros2_types_requested) = arg_matches.get_many::<String>("type") {
     let output_dir = arg_matches
       .get_one::<String>("output")
-      .ok_or(io::Error::new(io::ErrorKind::Other, "Output dir required"))?;
+      .ok_or(io::Error::other("Output dir required"))?;
     let workspace_dir = arg_matches
       .get_one::<String>("workspace")
-      .ok_or(io::Error::new(
-        io::ErrorKind::Other,
+      .ok_or(io::Error::other(
         "ROS 2 workspace dir required",
       ))?;
 
@@ -143,8 +137,7 @@ use std::{ffi::OsStr, path::PathBuf};
 use bstr::ByteSlice;
 
 fn list_packges_with_msgs(workspace_dir: &str, ros2_abs_type: &str) -> io::Result<Vec<RosPkg>> {
-  let (package_name, _type_name) = ros2_abs_type.rsplit_once('/').ok_or(io::Error::new(
-    io::ErrorKind::Other,
+  let (package_name, _type_name) = ros2_abs_type.rsplit_once('/').ok_or(io::Error::other(
     "Need package_name/type_name",
   ))?;
 
@@ -209,8 +202,7 @@ fn list_packges_with_msgs(workspace_dir: &str, ros2_abs_type: &str) -> io::Resul
     println!("Got {} packages", result.len());
     Ok(result)
   } else {
-    Err(io::Error::new(
-      io::ErrorKind::Other,
+    Err(io::Error::other(
       format!(
         "Colcon failure: {}\nHave you run local_setup.bash?",
         String::from_utf8_lossy(&colcon_output.stderr)
@@ -229,10 +221,10 @@ fn print_struct_definition<W: io::Write>(
 
   let not_yet = lines
     .iter()
-    .take_while(|p| p.0.as_ref().map_or(true, is_not_field));
+    .take_while(|p| p.0.as_ref().is_none_or(is_not_field));
   let got_field = lines
     .iter()
-    .skip_while(|p| p.0.as_ref().map_or(true, is_not_field));
+    .skip_while(|p| p.0.as_ref().is_none_or(is_not_field));
 
   for (item, comment) in not_yet {
     match (item, comment) {
@@ -24,6 +24,7 @@ use crate::{
   names::*,
   node::{Node, NodeOptions},
   pubsub::{Publisher, Subscription},
+  service::ServiceMapping,
   NodeCreateError,
 };
 
@@ -71,6 +72,8 @@ struct SecurityConfig {
 /// Builder for configuring a `Context`
 pub struct ContextOptions {
   domain_id: u16,
+  service_mapping: Option<ServiceMapping>,
+  passive_observer: bool,
   #[cfg(feature = "security")]
   security_config: Option<SecurityConfig>,
 }
@@ -79,6 +82,8 @@ impl ContextOptions {
   pub fn new() -> Self {
     Self {
       domain_id: 0,
+      service_mapping: None,
+      passive_observer: false,
       #[cfg(feature = "security")]
       security_config: None,
     }
@@ -94,6 +99,30 @@ impl ContextOptions {
     self
   }
 
+  /// Set the ServiceMapping used by built-in Services, such as Parameter
+  /// Services. The default is detected from environment variable
+  /// `RMW_IMPLEMENTATION`. See [`ServiceMapping::from_env`].
+  pub fn service_mapping(mut self, service_mapping: ServiceMapping) -> Self {
+    self.service_mapping = Some(service_mapping);
+    self
+  }
+
+  /// Observe ROS 2 Discovery without taking part in it.
+  ///
+  /// A passive observer Context receives `ros_discovery_info` and DDS
+  /// Discovery data as usual, but never publishes its own Nodes to
+  /// `ros_discovery_info`. This way monitoring tools do not show up in e.g.
+  /// `ros2 node list`. Likewise, graph introspection of its Nodes, e.g.
+  /// [`Node::graph_snapshot`], leaves out the observer's own Nodes and
+  /// endpoints.
+  ///
+  /// The DDS endpoints of Nodes are still visible in DDS Discovery, so
+  /// consider also disabling rosout with [`NodeOptions::enable_rosout`].
+  pub fn passive_observer(mut self, passive_observer: bool) -> Self {
+    self.passive_observer = passive_observer;
+    self
+  }
+
   /// Enable DDS security features.
   ///
   /// Using security requires providing appropriate configuration files.
@@ -153,7 +182,15 @@ impl Context {
       }
     }
 
-    Self::from_domain_participant(dpb.build()?)
+    let context = Self::from_domain_participant(dpb.build()?)?;
+    {
+      let mut inner = context.inner.lock().unwrap();
+      if let Some(service_mapping) = opt.service_mapping {
+        inner.service_mapping = service_mapping;
+      }
+      inner.passive_observer = opt.passive_observer;
+    }
+    Ok(context)
   }
 
   /// Create a new Context from an existing [`DomainParticipant`].
@@ -178,6 +215,18 @@ impl Context {
     self.inner.lock().unwrap().domain_participant.domain_id()
   }
 
+  /// Is this Context a passive observer of ROS 2 Discovery. See
+  /// [`ContextOptions::passive_observer`].
+  pub fn is_passive_observer(&self) -> bool {
+    self.inner.lock().unwrap().passive_observer
+  }
+
+  /// ServiceMapping for built-in Services, unless overridden in
+  /// [`NodeOptions`].
+  pub fn service_mapping(&self) -> ServiceMapping {
+    self.inner.lock().unwrap().service_mapping
+  }
+
   /// Which topics have been discovered?
   pub fn discovered_topics(&self) -> Vec<rustdds::discovery::DiscoveredTopicData> {
     self.domain_participant().discovered_topics()
@@ -303,6 +352,14 @@ impl Context {
     self.inner.lock().unwrap().remove_node(node_name);
   }
 
+  pub(crate) fn remove_node_endpoint(&self, node_name: &str, gid: Gid) {
+    self
+      .inner
+      .lock()
+      .unwrap()
+      .remove_node_endpoint(node_name, gid);
+  }
+
   fn get_ros_default_publisher(&self) -> rustdds::Publisher {
     self.inner.lock().unwrap().ros_default_publisher.clone()
   }
@@ -334,6 +391,10 @@ struct ContextInner {
 
   ros_parameter_events_topic: Topic,
   ros_rosout_topic: Topic,
+
+  service_mapping: ServiceMapping,
+  // Do not publish ros_discovery_info
+  passive_observer: bool,
 }
 
 impl ContextInner {
@@ -379,6 +440,8 @@ impl ContextInner {
       ros_default_subscriber,
       ros_parameter_events_topic,
       ros_rosout_topic,
+      service_mapping: ServiceMapping::from_env(),
+      passive_observer: false,
     })
   }
 
@@ -407,7 +470,23 @@ impl ContextInner {
     self.broadcast_node_infos();
   }
 
+  /// Removes a Reader or Writer from a Node and updates our ContextInfo to
+  /// ROS2 network
+  fn remove_node_endpoint(&mut self, node_fqn: &str, gid: Gid) {
+    let removed = self
+      .local_nodes
+      .get_mut(node_fqn)
+      .map(|node_info| node_info.remove_endpoint(gid))
+      .unwrap_or(false);
+    if removed {
+      self.broadcast_node_infos();
+    }
+  }
+
   fn broadcast_node_infos(&self) {
+    if self.passive_observer {
+      return;
+    }
     let pei = self.participant_entities_info();
     debug!("ROS discovery publish: {pei:?}");
     self
@@ -440,3 +519,51 @@ fn test_node_create() {
     )
     .is_ok();
 }
+
+#[test]
+fn test_dropped_publisher_is_removed_from_node_info() {
+  let context = Context::new().unwrap();
+  let mut node = context
+    .new_node(
+      NodeName::new("/rustdds", "test_drop_node").unwrap(),
+      NodeOptions::new(),
+    )
+    .unwrap();
+  let topic = node
+    .create_topic(
+      &Name::new("/", "test_drop_topic").unwrap(),
+      MessageTypeName::new("std_msgs", "String"),
+      &DEFAULT_PUBLISHER_QOS,
+    )
+    .unwrap();
+  let publisher = node.create_publisher::<String>(&topic, None).unwrap();
+  let gid = publisher.gid();
+  let writers = |context: &Context| -> Vec<Gid> {
+    context
+      .participant_entities_info()
+      .nodes()
+      .iter()
+      .flat_map(|info| info.writers().to_vec())
+      .collect()
+  };
+  assert!(writers(&context).contains(&gid));
+
+  drop(publisher);
+  assert!(!writers(&context).contains(&gid));
+}
+
+#[test]
+fn test_service_mapping_option() {
+  assert_eq!(
+    ServiceMapping::for_rmw_implementation("rmw_cyclonedds_cpp"),
+    ServiceMapping::Cyclone
+  );
+  assert_eq!(
+    ServiceMapping::for_rmw_implementation("rmw_fastrtps_cpp"),
+    ServiceMapping::Enhanced
+  );
+
+  let context =
+    Context::with_options(ContextOptions::new().service_mapping(ServiceMapping::Cyclone)).unwrap();
+  assert_eq!(context.service_mapping(), ServiceMapping::Cyclone);
+}
@@ -0,0 +1,353 @@
+use std::{collections::BTreeMap, error::Error, fmt, sync::Arc};
+
+use crate::names::MessageTypeName;
+use super::{
+  message::{DynamicMessage, Value},
+  parser::{msg_spec, ArraySpecifier, BaseTypeName, Item, TypeName},
+};
+
+/// Run-time description of a message type, i.e. the contents of a `.msg`
+/// file with all nested types resolved.
+#[derive(Debug, Clone)]
+pub struct MessageDefinition {
+  type_name: MessageTypeName,
+  fields: Vec<FieldDefinition>,
+}
+
+/// A single field of a [`MessageDefinition`]
+#[derive(Debug, Clone)]
+pub struct FieldDefinition {
+  pub name: String,
+  pub field_type: FieldType,
+}
+
+/// Type of a message field.
+///
+/// Bounds of strings and sequences are not represented, i.e. they are not
+/// checked.
+#[derive(Debug, Clone)]
+pub enum FieldType {
+  Bool,
+  Byte,
+  Char,
+  Float32,
+  Float64,
+  Int8,
+  Int16,
+  Int32,
+  Int64,
+  UInt8,
+  UInt16,
+  UInt32,
+  UInt64,
+  String,
+  WString,
+  Message(Arc<MessageDefinition>),
+  /// Fixed-size array, e.g. `float64[9]`
+  Array(Box<FieldType>, usize),
+  /// Variable-length sequence, e.g. `float64[]` or `float64[<=9]`
+  Sequence(Box<FieldType>),
+}
+
+impl FieldType {
+  /// Value of this type, when nothing else is specified: zero, empty string,
+  /// or empty sequence.
+  pub fn default_value(&self) -> Value {
+    match self {
+      FieldType::Bool => Value::Bool(false),
+      FieldType::Byte => Value::Byte(0),
+      FieldType::Char => Value::Char(0),
+      FieldType::Float32 => Value::Float32(0.0),
+      FieldType::Float64 => Value::Float64(0.0),
+      FieldType::Int8 => Value::Int8(0),
+      FieldType::Int16 => Value::Int16(0),
+      FieldType::Int32 => Value::Int32(0),
+      FieldType::Int64 => Value::Int64(0),
+      FieldType::UInt8 => Value::UInt8(0),
+      FieldType::UInt16 => Value::UInt16(0),
+      FieldType::UInt32 => Value::UInt32(0),
+      FieldType::UInt64 => Value::UInt64(0),
+      FieldType::String => Value::String(String::new()),
+      FieldType::WString => Value::WString(String::new()),
+      FieldType::Message(definition) => Value::Message(definition.default_message()),
+      FieldType::Array(element, size) => Value::Array(vec![element.default_value(); *size]),
+      FieldType::Sequence(_) => Value::Array(Vec::new()),
+    }
+  }
+}
+
+impl fmt::Display for FieldType {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FieldType::Bool => write!(f, "bool"),
+      FieldType::Byte => write!(f, "byte"),
+      FieldType::Char => write!(f, "char"),
+      FieldType::Float32 => write!(f, "float32"),
+      FieldType::Float64 => write!(f, "float64"),
+      FieldType::Int8 => write!(f, "int8"),
+      FieldType::Int16 => write!(f, "int16"),
+      FieldType::Int32 => write!(f, "int32"),
+      FieldType::Int64 => write!(f, "int64"),
+      FieldType::UInt8 => write!(f, "uint8"),
+      FieldType::UInt16 => write!(f, "uint16"),
+      FieldType::UInt32 => write!(f, "uint32"),
+      FieldType::UInt64 => write!(f, "uint64"),
+      FieldType::String => write!(f, "string"),
+      FieldType::WString => write!(f, "wstring"),
+      FieldType::Message(definition) => write!(
+        f,
+        "{}/{}",
+        definition.type_name.package_name(),
+        definition.type_name.type_name()
+      ),
+      FieldType::Array(element, size) => write!(f, "{element}[{size}]"),
+      FieldType::Sequence(element) => write!(f, "{element}[]"),
+    }
+  }
+}
+
+/// Error from parsing a [`MessageDefinition`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionError {
+  /// The definition of `type_name` could not be parsed at `line` (1-based)
+  /// of the text given to [`MessageDefinition::parse`].
+  Syntax { type_name: String, line: usize },
+  /// A field has a type that is not defined.
+  UnknownType { type_name: String },
+  /// The message type contains itself.
+  Recursive { type_name: String },
+}
+
+impl fmt::Display for DefinitionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Syntax { type_name, line } => {
+        write!(f, "DefinitionError::Syntax : {type_name} line {line}")
+      }
+      Self::UnknownType { type_name } => write!(f, "DefinitionError::UnknownType : {type_name}"),
+      Self::Recursive { type_name } => write!(f, "DefinitionError::Recursive : {type_name}"),
+    }
+  }
+}
+
+impl Error for DefinitionError {}
+
+impl MessageDefinition {
+  /// Parse the definition of `type_name` from `.msg` text.
+  ///
+  /// The text may contain definitions of nested types after the main
+  /// definition, in the concatenated format used e.g. by rosbag2: Each
+  /// definition is preceded by a line of `=` characters and a line
+  /// `MSG: package/Type`. Nested types in `builtin_interfaces` need not be
+  /// included.
+  ///
+  /// The grammar is the same as in `msggen`. Constants are accepted, but
+  /// skipped. Default values of fields are not supported.
+  pub fn parse(
+    type_name: &MessageTypeName,
+    text: &str,
+  ) -> Result<MessageDefinition, DefinitionError> {
+    let top = qualified_name(type_name.package_name(), type_name.type_name());
+    let mut sources = BTreeMap::new();
+    let mut current = top.clone();
+    let mut section = Source {
+      first_line: 1,
+      text: String::new(),
+    };
+    let mut lines = text.lines().enumerate();
+    while let Some((line_number, line)) = lines.next() {
+      if line.len() >= 3 && line.trim_end().chars().all(|c| c == '=') {
+        sources.insert(current, section);
+        section = Source {
+          first_line: line_number + 3, // after separator and MSG line
+          text: String::new(),
+        };
+        current = lines
+          .next()
+          .and_then(|(_, l)| l.trim().strip_prefix("MSG:"))
+          .map(|name| name.trim().replace("/msg/", "/"))
+          .ok_or_else(|| DefinitionError::Syntax {
+            type_name: top.clone(),
+            line: line_number + 2, // the line after separator
+          })?;
+      } else {
+        section.text.push_str(line);
+        section.text.push('\n');
+      }
+    }
+    sources.insert(current, section);
+
+    let mut resolver = Resolver {
+      sources,
+      resolved: BTreeMap::new(),
+      in_progress: Vec::new(),
+    };
+    let definition = resolver.resolve(&top)?;
+    Ok(MessageDefinition::clone(&definition))
+  }
+
+  pub fn type_name(&self) -> &MessageTypeName {
+    &self.type_name
+  }
+
+  pub fn fields(&self) -> &[FieldDefinition] {
+    &self.fields
+  }
+
+  /// A message of this type with all fields set to their default values.
+  pub fn default_message(&self) -> DynamicMessage {
+    let mut message = DynamicMessage::new();
+    for field in &self.fields {
+      message.set(&field.name, field.field_type.default_value());
+    }
+    message
+  }
+}
+
+fn qualified_name(package_name: &str, type_name: &str) -> String {
+  format!("{package_name}/{type_name}")
+}
+
+// Nested types that are used everywhere, but may be left out of
+// concatenated definitions.
+fn builtin_source(qualified_name: &str) -> Option<&'static str> {
+  match qualified_name {
+    "builtin_interfaces/Time" | "builtin_interfaces/Duration" => {
+      Some("int32 sec\nuint32 nanosec\n")
+    }
+    _ => None,
+  }
+}
+
+// Definition of one type in the text given to MessageDefinition::parse
+#[derive(Clone)]
+struct Source {
+  first_line: usize, // line number of text in the whole input
+  text: String,
+}
+
+struct Resolver {
+  sources: BTreeMap<String, Source>, // qualified type name --> .msg text
+  resolved: BTreeMap<String, Arc<MessageDefinition>>,
+  in_progress: Vec<String>, // to detect recursion
+}
+
+impl Resolver {
+  fn resolve(&mut self, name: &str) -> Result<Arc<MessageDefinition>, DefinitionError> {
+    if let Some(definition) = self.resolved.get(name) {
+      return Ok(definition.clone());
+    }
+    if self.in_progress.iter().any(|n| n == name) {
+      return Err(DefinitionError::Recursive {
+        type_name: name.to_string(),
+      });
+    }
+    let source = match self.sources.get(name) {
+      Some(s) => s.clone(),
+      None => Source {
+        first_line: 1,
+        text: builtin_source(name)
+          .ok_or_else(|| DefinitionError::UnknownType {
+            type_name: name.to_string(),
+          })?
+          .to_string(),
+      },
+    };
+    let (package_name, type_name) = name.split_once('/').unwrap_or(("", name));
+
+    // The grammar is line-based, so parse one line at a time to know where a
+    // syntax error is.
+    let mut items = Vec::new();
+    for (i, line) in source.text.lines().enumerate() {
+      match msg_spec(&format!("{line}\n")) {
+        Ok(("", line_items)) => items.extend(line_items),
+        _ => {
+          return Err(DefinitionError::Syntax {
+            type_name: name.to_string(),
+            line: source.first_line + i,
+          })
+        }
+      }
+    }
+
+    self.in_progress.push(name.to_string());
+    let mut fields = Vec::new();
+    for item in items.into_iter().filter_map(|(item, _comment)| item) {
+      if let Item::Field {
+        type_name: field_type_name,
+        field_name,
+        ..
+      } = item
+      {
+        fields.push(FieldDefinition {
+          name: field_name,
+          field_type: self.field_type(package_name, &field_type_name)?,
+        });
+      }
+    }
+    self.in_progress.pop();
+
+    let definition = Arc::new(MessageDefinition {
+      type_name: MessageTypeName::new(package_name, type_name),
+      fields,
+    });
+    self.resolved.insert(name.to_string(), definition.clone());
+    Ok(definition)
+  }
+
+  fn field_type(
+    &mut self,
+    package_name: &str,
+    type_name: &TypeName,
+  ) -> Result<FieldType, DefinitionError> {
+    let base = match &type_name.base {
+      BaseTypeName::Primitive { name } => match name.as_str() {
+        "bool" => FieldType::Bool,
+        "byte" => FieldType::Byte,
+        "char" => FieldType::Char,
+        "float32" => FieldType::Float32,
+        "float64" => FieldType::Float64,
+        "int8" => FieldType::Int8,
+        "int16" => FieldType::Int16,
+        "int32" => FieldType::Int32,
+        "int64" => FieldType::Int64,
+        "uint8" => FieldType::UInt8,
+        "uint16" => FieldType::UInt16,
+        "uint32" => FieldType::UInt32,
+        "uint64" => FieldType::UInt64,
+        "string" => FieldType::String,
+        "wstring" => FieldType::WString,
+        other => {
+          return Err(DefinitionError::UnknownType {
+            type_name: other.to_string(),
+          })
+        }
+      },
+      BaseTypeName::BoundedString { .. } => FieldType::String,
+      BaseTypeName::ComplexType {
+        package_name: Some(package),
+        type_name,
+      } => FieldType::Message(self.resolve(&qualified_name(package, type_name))?),
+      BaseTypeName::ComplexType {
+        package_name: None,
+        type_name,
+      } => {
+        // Unqualified "Header" is special, others are from the same package.
+        let package = if type_name == "Header" {
+          "std_msgs"
+        } else {
+          package_name
+        };
+        FieldType::Message(self.resolve(&qualified_name(package, type_name))?)
+      }
+    };
+
+    Ok(match type_name.array_spec {
+      None => base,
+      Some(ArraySpecifier::Static { size }) => FieldType::Array(Box::new(base), size as usize),
+      Some(ArraySpecifier::Unbounded) | Some(ArraySpecifier::Bounded { .. }) => {
+        FieldType::Sequence(Box::new(base))
+      }
+    })
+  }
+}
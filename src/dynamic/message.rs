@@ -0,0 +1,72 @@
+/// A message whose type is known only at run time.
+///
+/// Fields are named and kept in the order of the [`MessageDefinition`]
+/// (when received), or in the order they were set.
+///
+/// [`MessageDefinition`]: super::MessageDefinition
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DynamicMessage {
+  fields: Vec<(String, Value)>,
+}
+
+impl DynamicMessage {
+  pub fn new() -> DynamicMessage {
+    DynamicMessage { fields: Vec::new() }
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Value> {
+    self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+  }
+
+  pub fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+    self
+      .fields
+      .iter_mut()
+      .find(|(n, _)| n == name)
+      .map(|(_, v)| v)
+  }
+
+  /// Set a field value. Returns the previous value, if the field was already
+  /// present.
+  pub fn set(&mut self, name: &str, value: Value) -> Option<Value> {
+    match self.get_mut(name) {
+      Some(old) => Some(std::mem::replace(old, value)),
+      None => {
+        self.fields.push((name.to_string(), value));
+        None
+      }
+    }
+  }
+
+  pub fn fields(&self) -> impl Iterator<Item = (&str, &Value)> {
+    self.fields.iter().map(|(n, v)| (n.as_str(), v))
+  }
+
+  pub(crate) fn push(&mut self, name: String, value: Value) {
+    self.fields.push((name, value));
+  }
+}
+
+/// Value of a [`DynamicMessage`] field.
+///
+/// Both fixed-size arrays and sequences are represented as `Array`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Bool(bool),
+  Byte(u8),
+  Char(u8),
+  Float32(f32),
+  Float64(f64),
+  Int8(i8),
+  Int16(i16),
+  Int32(i32),
+  Int64(i64),
+  UInt8(u8),
+  UInt16(u16),
+  UInt32(u32),
+  UInt64(u64),
+  String(String),
+  WString(String),
+  Message(DynamicMessage),
+  Array(Vec<Value>),
+}
@@ -0,0 +1,354 @@
+//! Subscriptions and Publishers for message types that are known only at
+//! run time.
+//!
+//! This is meant for tools, such as recorders and bridges, that cannot have
+//! the message types compiled in. The type is given as a
+//! [`MessageDefinition`], which is parsed from `.msg` text with the same
+//! grammar as `msggen` uses. Messages are [`DynamicMessage`] value trees.
+//!
+//! See [`Node::create_dynamic_subscription`] and
+//! [`Node::create_dynamic_publisher`].
+//!
+//! [`Node::create_dynamic_subscription`]: crate::Node::create_dynamic_subscription
+//! [`Node::create_dynamic_publisher`]: crate::Node::create_dynamic_publisher
+use std::sync::Arc;
+
+use futures::{stream::FusedStream, Future};
+use rustdds::dds::{ReadResult, WriteError, WriteResult};
+
+use crate::{
+  gid::Gid,
+  message_info::MessageInfo,
+  node::{DiscoveryError, Node},
+  pubsub::{Publisher, Subscription},
+};
+
+mod definition;
+mod message;
+// The .msg grammar. This is public for msggen, but not a stable API.
+#[doc(hidden)]
+pub mod parser;
+mod serialization;
+mod stringparser;
+
+pub use definition::{DefinitionError, FieldDefinition, FieldType, MessageDefinition};
+pub use message::{DynamicMessage, Value};
+use serialization::{EncodedMessage, MessageSeed};
+
+/// Subscription that decodes messages according to a [`MessageDefinition`].
+pub struct DynamicSubscription {
+  subscription: Subscription<DynamicMessage>,
+  definition: Arc<MessageDefinition>,
+}
+
+impl DynamicSubscription {
+  pub(crate) fn new(
+    subscription: Subscription<DynamicMessage>,
+    definition: MessageDefinition,
+  ) -> DynamicSubscription {
+    DynamicSubscription {
+      subscription,
+      definition: Arc::new(definition),
+    }
+  }
+
+  pub fn take(&self) -> ReadResult<Option<(DynamicMessage, MessageInfo)>> {
+    self.subscription.take_seed(MessageSeed(&self.definition))
+  }
+
+  pub fn async_stream(
+    &self,
+  ) -> impl FusedStream<Item = ReadResult<(DynamicMessage, MessageInfo)>> + '_ {
+    self
+      .subscription
+      .async_stream_seed(MessageSeed(&self.definition))
+  }
+
+  pub fn definition(&self) -> &MessageDefinition {
+    &self.definition
+  }
+
+  /// Access the underlying Subscription, e.g. for counting publishers.
+  pub fn subscription(&self) -> &Subscription<DynamicMessage> {
+    &self.subscription
+  }
+}
+
+/// Publisher that encodes messages according to a [`MessageDefinition`].
+///
+/// Publishing fails with a serialization error, if the message does not
+/// match the definition.
+pub struct DynamicPublisher {
+  publisher: Publisher<EncodedMessage>,
+  definition: Arc<MessageDefinition>,
+}
+
+impl DynamicPublisher {
+  pub(crate) fn new(
+    publisher: Publisher<EncodedMessage>,
+    definition: MessageDefinition,
+  ) -> DynamicPublisher {
+    DynamicPublisher {
+      publisher,
+      definition: Arc::new(definition),
+    }
+  }
+
+  fn encode(&self, message: DynamicMessage) -> EncodedMessage {
+    EncodedMessage {
+      definition: self.definition.clone(),
+      message,
+    }
+  }
+
+  pub fn publish(&self, message: DynamicMessage) -> WriteResult<(), DynamicMessage> {
+    self
+      .publisher
+      .publish(self.encode(message))
+      .map_err(decoded_write_error)
+  }
+
+  pub async fn async_publish(&self, message: DynamicMessage) -> WriteResult<(), DynamicMessage> {
+    self
+      .publisher
+      .async_publish(self.encode(message))
+      .await
+      .map_err(decoded_write_error)
+  }
+
+  pub fn definition(&self) -> &MessageDefinition {
+    &self.definition
+  }
+
+  pub fn guid(&self) -> rustdds::GUID {
+    self.publisher.guid()
+  }
+
+  pub fn gid(&self) -> Gid {
+    self.publisher.gid()
+  }
+
+  /// See [`Publisher::get_subscription_count`]
+  pub fn get_subscription_count(&self, my_node: &Node) -> usize {
+    self.publisher.get_subscription_count(my_node)
+  }
+
+  /// See [`Publisher::wait_for_subscription`]
+  pub fn wait_for_subscription(
+    &self,
+    my_node: &Node,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
+    self.publisher.wait_for_subscription(my_node)
+  }
+}
+
+// Give the DynamicMessage back to the caller, without the definition.
+fn decoded_write_error(e: WriteError<EncodedMessage>) -> WriteError<DynamicMessage> {
+  match e {
+    WriteError::Serialization { reason, data } => WriteError::Serialization {
+      reason,
+      data: data.message,
+    },
+    WriteError::Poisoned { reason, data } => WriteError::Poisoned {
+      reason,
+      data: data.message,
+    },
+    WriteError::Io(e) => WriteError::Io(e),
+    WriteError::WouldBlock { data } => WriteError::WouldBlock { data: data.message },
+    WriteError::Internal { reason } => WriteError::Internal { reason },
+  }
+}
+
+// ----------------------------------------------------
+// ----------------------------------------------------
+
+#[cfg(test)]
+mod test {
+  use serde::{Deserialize, Serialize};
+  use rustdds::serialization::{
+    deserialize_from_cdr_with_decoder_and_rep_id, to_writer_with_rep_id, RepresentationIdentifier,
+  };
+
+  use super::*;
+  use crate::{builtin_interfaces::Time, names::MessageTypeName, WString};
+
+  // What msggen would generate for the test types
+  #[derive(Serialize, Deserialize)]
+  struct Inner {
+    flag: bool,
+    names: Vec<String>,
+  }
+
+  #[derive(Serialize, Deserialize)]
+  struct Outer {
+    stamp: Time,
+    id: u8,
+    position: [f64; 3],
+    label: WString,
+    inners: Vec<Inner>,
+    count: i64,
+  }
+
+  const OUTER_MSG: &str = "\
+# A test message
+builtin_interfaces/Time stamp
+uint8 id
+float64[3] position
+wstring label
+int32 CONSTANT=7
+Inner[] inners
+int64 count # last one
+================================================================================
+MSG: test_msgs/Inner
+bool flag
+string<=10[<=2] names
+";
+
+  fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    to_writer_with_rep_id(&mut bytes, value, RepresentationIdentifier::CDR_LE).unwrap();
+    bytes
+  }
+
+  #[test]
+  fn test_dynamic_message_round_trip() {
+    let definition =
+      MessageDefinition::parse(&MessageTypeName::new("test_msgs", "Outer"), OUTER_MSG).unwrap();
+    let field_types: Vec<String> = definition
+      .fields()
+      .iter()
+      .map(|f| f.field_type.to_string())
+      .collect();
+    assert_eq!(
+      field_types,
+      vec![
+        "builtin_interfaces/Time",
+        "uint8",
+        "float64[3]",
+        "wstring",
+        "test_msgs/Inner[]",
+        "int64"
+      ]
+    );
+
+    let outer = Outer {
+      stamp: Time::from_nanos(12_000_000_345),
+      id: 7,
+      position: [1.0, 2.5, -3.0],
+      label: WString::from(widestring::Utf16String::from_str("wide")),
+      inners: vec![
+        Inner {
+          flag: true,
+          names: vec!["a".to_string(), "bc".to_string()],
+        },
+        Inner {
+          flag: false,
+          names: vec![],
+        },
+      ],
+      count: -9,
+    };
+    let bytes = encode(&outer);
+
+    let (message, _) = deserialize_from_cdr_with_decoder_and_rep_id(
+      &bytes,
+      RepresentationIdentifier::CDR_LE,
+      MessageSeed(&definition),
+    )
+    .unwrap();
+    match message.get("stamp") {
+      Some(Value::Message(stamp)) => assert_eq!(stamp.get("nanosec"), Some(&Value::UInt32(345))),
+      other => panic!("Unexpected {:?}", other),
+    }
+    assert_eq!(message.get("id"), Some(&Value::UInt8(7)));
+    assert_eq!(
+      message.get("label"),
+      Some(&Value::WString("wide".to_string()))
+    );
+    match message.get("inners") {
+      Some(Value::Array(inners)) => {
+        assert_eq!(inners.len(), 2);
+        match &inners[0] {
+          Value::Message(inner) => assert_eq!(
+            inner.get("names"),
+            Some(&Value::Array(vec![
+              Value::String("a".to_string()),
+              Value::String("bc".to_string())
+            ]))
+          ),
+          other => panic!("Unexpected {:?}", other),
+        }
+      }
+      other => panic!("Unexpected {:?}", other),
+    }
+
+    // Encoding back gives the same bytes
+    let encoded = EncodedMessage {
+      definition: Arc::new(definition.clone()),
+      message,
+    };
+    assert_eq!(encode(&encoded), bytes);
+
+    // Default message has right shape, but mismatching values do not encode
+    let mut message = definition.default_message();
+    assert_eq!(
+      message.get("position"),
+      Some(&Value::Array(vec![Value::Float64(0.0); 3]))
+    );
+    message.set("count", Value::Int32(1));
+    let mut bytes = Vec::new();
+    assert!(to_writer_with_rep_id(
+      &mut bytes,
+      &EncodedMessage {
+        definition: Arc::new(definition),
+        message,
+      },
+      RepresentationIdentifier::CDR_LE
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_message_definition_errors() {
+    let type_name = MessageTypeName::new("test_msgs", "Bad");
+    assert_eq!(
+      MessageDefinition::parse(&type_name, "int32 a\nint32 b 5\n").unwrap_err(),
+      DefinitionError::Syntax {
+        type_name: "test_msgs/Bad".to_string(),
+        line: 2
+      }
+    );
+    // Line numbers count from the start of the whole text.
+    assert_eq!(
+      MessageDefinition::parse(&type_name, "int32 a\nint32 b\nstring S=\"abc\n").unwrap_err(),
+      DefinitionError::Syntax {
+        type_name: "test_msgs/Bad".to_string(),
+        line: 3
+      }
+    );
+    let nested = "Inner inner\n\
+                  ================================================================================\n\
+                  MSG: test_msgs/Inner\n\
+                  int32 x\n\
+                  int32 y 5\n";
+    assert_eq!(
+      MessageDefinition::parse(&type_name, nested).unwrap_err(),
+      DefinitionError::Syntax {
+        type_name: "test_msgs/Inner".to_string(),
+        line: 5
+      }
+    );
+    assert_eq!(
+      MessageDefinition::parse(&type_name, "Missing m").unwrap_err(),
+      DefinitionError::UnknownType {
+        type_name: "test_msgs/Missing".to_string()
+      }
+    );
+    assert_eq!(
+      MessageDefinition::parse(&type_name, "Bad[] children").unwrap_err(),
+      DefinitionError::Recursive {
+        type_name: "test_msgs/Bad".to_string()
+      }
+    );
+  }
+}
@@ -0,0 +1,212 @@
+// Serde (de)serialization of DynamicMessages, driven by a MessageDefinition.
+//
+// This mirrors what derived implementations would do for the equivalent
+// msggen-generated struct, so that the CDR encoding is the same.
+use std::{fmt, sync::Arc};
+
+use serde::{
+  de::{self, DeserializeSeed, SeqAccess, Visitor},
+  ser::{self, SerializeSeq, SerializeTuple},
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{
+  definition::{FieldType, MessageDefinition},
+  message::{DynamicMessage, Value},
+};
+
+// Upper limit for preallocation, so that a bogus sequence length in incoming
+// data does not make us allocate a lot of memory.
+const MAX_PREALLOCATION: usize = 4096;
+
+#[derive(Clone, Copy)]
+pub(crate) struct MessageSeed<'a>(pub &'a MessageDefinition);
+
+impl<'de, 'a> DeserializeSeed<'de> for MessageSeed<'a> {
+  type Value = DynamicMessage;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<DynamicMessage, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    if self.0.fields().is_empty() {
+      // Empty message types are encoded as a single dummy byte.
+      u8::deserialize(deserializer)?;
+      Ok(DynamicMessage::new())
+    } else {
+      deserializer.deserialize_tuple(self.0.fields().len(), self)
+    }
+  }
+}
+
+impl<'de, 'a> Visitor<'de> for MessageSeed<'a> {
+  type Value = DynamicMessage;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    let type_name = self.0.type_name();
+    write!(
+      formatter,
+      "message {}/{}",
+      type_name.package_name(),
+      type_name.type_name()
+    )
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<DynamicMessage, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    let mut message = DynamicMessage::new();
+    for (i, field) in self.0.fields().iter().enumerate() {
+      let value = seq
+        .next_element_seed(ValueSeed(&field.field_type))?
+        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+      message.push(field.name.clone(), value);
+    }
+    Ok(message)
+  }
+}
+
+#[derive(Clone, Copy)]
+struct ValueSeed<'a>(&'a FieldType);
+
+impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+  type Value = Value;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    Ok(match self.0 {
+      FieldType::Bool => Value::Bool(bool::deserialize(deserializer)?),
+      FieldType::Byte => Value::Byte(u8::deserialize(deserializer)?),
+      FieldType::Char => Value::Char(u8::deserialize(deserializer)?),
+      FieldType::Float32 => Value::Float32(f32::deserialize(deserializer)?),
+      FieldType::Float64 => Value::Float64(f64::deserialize(deserializer)?),
+      FieldType::Int8 => Value::Int8(i8::deserialize(deserializer)?),
+      FieldType::Int16 => Value::Int16(i16::deserialize(deserializer)?),
+      FieldType::Int32 => Value::Int32(i32::deserialize(deserializer)?),
+      FieldType::Int64 => Value::Int64(i64::deserialize(deserializer)?),
+      FieldType::UInt8 => Value::UInt8(u8::deserialize(deserializer)?),
+      FieldType::UInt16 => Value::UInt16(u16::deserialize(deserializer)?),
+      FieldType::UInt32 => Value::UInt32(u32::deserialize(deserializer)?),
+      FieldType::UInt64 => Value::UInt64(u64::deserialize(deserializer)?),
+      FieldType::String => Value::String(String::deserialize(deserializer)?),
+      FieldType::WString => {
+        let utf16 = Vec::<u16>::deserialize(deserializer)?;
+        Value::WString(String::from_utf16_lossy(&utf16))
+      }
+      FieldType::Message(definition) => {
+        Value::Message(MessageSeed(definition).deserialize(deserializer)?)
+      }
+      FieldType::Array(element, size) => {
+        deserializer.deserialize_tuple(*size, ArrayVisitor(element))?
+      }
+      FieldType::Sequence(element) => deserializer.deserialize_seq(ArrayVisitor(element))?,
+    })
+  }
+}
+
+struct ArrayVisitor<'a>(&'a FieldType);
+
+impl<'de, 'a> Visitor<'de> for ArrayVisitor<'a> {
+  type Value = Value;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    write!(formatter, "array of {}", self.0)
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(MAX_PREALLOCATION));
+    while let Some(value) = seq.next_element_seed(ValueSeed(self.0))? {
+      values.push(value);
+    }
+    Ok(Value::Array(values))
+  }
+}
+
+// A DynamicMessage together with its type, so that it can be serialized.
+pub(crate) struct EncodedMessage {
+  pub definition: Arc<MessageDefinition>,
+  pub message: DynamicMessage,
+}
+
+impl Serialize for EncodedMessage {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    MessageEncoder(&self.definition, &self.message).serialize(serializer)
+  }
+}
+
+struct MessageEncoder<'a>(&'a MessageDefinition, &'a DynamicMessage);
+
+impl<'a> Serialize for MessageEncoder<'a> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let MessageEncoder(definition, message) = self;
+    if definition.fields().is_empty() {
+      return serializer.serialize_u8(0);
+    }
+    let mut tuple = serializer.serialize_tuple(definition.fields().len())?;
+    for field in definition.fields() {
+      let value = message.get(&field.name).ok_or_else(|| {
+        ser::Error::custom(format!(
+          "{}: missing field {}",
+          definition.type_name().type_name(),
+          field.name
+        ))
+      })?;
+      tuple.serialize_element(&ValueEncoder(&field.field_type, value))?;
+    }
+    tuple.end()
+  }
+}
+
+struct ValueEncoder<'a>(&'a FieldType, &'a Value);
+
+impl<'a> Serialize for ValueEncoder<'a> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match (self.0, self.1) {
+      (FieldType::Bool, Value::Bool(v)) => serializer.serialize_bool(*v),
+      (FieldType::Byte, Value::Byte(v)) => serializer.serialize_u8(*v),
+      (FieldType::Char, Value::Char(v)) => serializer.serialize_u8(*v),
+      (FieldType::Float32, Value::Float32(v)) => serializer.serialize_f32(*v),
+      (FieldType::Float64, Value::Float64(v)) => serializer.serialize_f64(*v),
+      (FieldType::Int8, Value::Int8(v)) => serializer.serialize_i8(*v),
+      (FieldType::Int16, Value::Int16(v)) => serializer.serialize_i16(*v),
+      (FieldType::Int32, Value::Int32(v)) => serializer.serialize_i32(*v),
+      (FieldType::Int64, Value::Int64(v)) => serializer.serialize_i64(*v),
+      (FieldType::UInt8, Value::UInt8(v)) => serializer.serialize_u8(*v),
+      (FieldType::UInt16, Value::UInt16(v)) => serializer.serialize_u16(*v),
+      (FieldType::UInt32, Value::UInt32(v)) => serializer.serialize_u32(*v),
+      (FieldType::UInt64, Value::UInt64(v)) => serializer.serialize_u64(*v),
+      (FieldType::String, Value::String(v)) => serializer.serialize_str(v),
+      (FieldType::WString, Value::WString(v)) => {
+        // Length must be known before serializing, so collect first.
+        let utf16: Vec<u16> = v.encode_utf16().collect();
+        utf16.serialize(serializer)
+      }
+      (FieldType::Message(definition), Value::Message(message)) => {
+        MessageEncoder(definition, message).serialize(serializer)
+      }
+      (FieldType::Array(element, size), Value::Array(values)) if values.len() == *size => {
+        let mut tuple = serializer.serialize_tuple(*size)?;
+        for value in values {
+          tuple.serialize_element(&ValueEncoder(element, value))?;
+        }
+        tuple.end()
+      }
+      (FieldType::Sequence(element), Value::Array(values)) => {
+        let mut seq = serializer.serialize_seq(Some(values.len()))?;
+        for value in values {
+          seq.serialize_element(&ValueEncoder(element, value))?;
+        }
+        seq.end()
+      }
+      (field_type, _) => Err(ser::Error::custom(format!(
+        "Value does not match field type {field_type}"
+      ))),
+    }
+  }
+}
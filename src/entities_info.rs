@@ -84,6 +84,20 @@ impl NodeEntitiesInfo {
     self.name.fully_qualified_name()
   }
 
+  pub fn node_name(&self) -> &NodeName {
+    &self.name
+  }
+
+  /// DDS Readers (Subscriptions) of this Node
+  pub fn readers(&self) -> &[Gid] {
+    &self.reader_gid_seq
+  }
+
+  /// DDS Writers (Publishers) of this Node
+  pub fn writers(&self) -> &[Gid] {
+    &self.writer_gid_seq
+  }
+
   pub fn add_writer(&mut self, gid: Gid) {
     if !self.writer_gid_seq.contains(&gid) {
       self.writer_gid_seq.push(gid);
@@ -95,6 +109,14 @@ impl NodeEntitiesInfo {
       self.reader_gid_seq.push(gid);
     }
   }
+
+  /// Remove a Reader or Writer. Returns `true`, if it was present.
+  pub fn remove_endpoint(&mut self, gid: Gid) -> bool {
+    let count = self.reader_gid_seq.len() + self.writer_gid_seq.len();
+    self.reader_gid_seq.retain(|g| *g != gid);
+    self.writer_gid_seq.retain(|g| *g != gid);
+    count != self.reader_gid_seq.len() + self.writer_gid_seq.len()
+  }
 }
 
 impl TryFrom<repr::NodeEntitiesInfo> for NodeEntitiesInfo {
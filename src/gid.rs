@@ -1,6 +1,7 @@
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
+use cdr_encoding_size::CdrEncodingSize;
 use rustdds::*;
 
 #[cfg(not(feature = "pre-iron-gid"))]
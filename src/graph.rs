@@ -0,0 +1,607 @@
+//! Introspection of the ROS 2 graph, i.e. which Nodes, Topics, Services and
+//! Actions exist.
+//!
+//! ROS 2 entities are mapped onto DDS Topics with mangled names, e.g. Topic
+//! `/chatter` of type `std_msgs/msg/String` is DDS Topic `rt/chatter` of type
+//! `std_msgs::msg::dds_::String_`. The functions here translate them back to
+//! ROS 2 names, as shown by e.g. `ros2 topic list -t`.
+//!
+//! See [Topic and Service name mapping to DDS](https://design.ros2.org/articles/topic_and_service_names.html)
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  fmt::Write,
+};
+
+use rustdds::{EndpointDescription, QosPolicies};
+use serde::{Deserialize, Serialize};
+
+use crate::{entities_info::NodeEntitiesInfo, gid::Gid, names::NodeName};
+
+/// Names of Topics, Services, or Actions, and the types used with each of
+/// them. Normally there is only one type per name, but nothing prevents
+/// different types from being used under the same name.
+pub type NamesAndTypes = BTreeMap<String, Vec<String>>;
+
+/// Is an endpoint a Publisher or a Subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndpointKind {
+  Publisher,
+  Subscription,
+}
+
+/// Information about a Publisher or Subscription of a Topic.
+///
+/// Similar to `TopicEndpointInfo` in rclcpp.
+#[derive(Debug, Clone)]
+pub struct TopicEndpointInfo {
+  /// Node that owns the endpoint, or `None` if it is not known, e.g. for a
+  /// plain DDS application.
+  pub node_name: Option<NodeName>,
+  pub endpoint_kind: EndpointKind,
+  pub endpoint_gid: Gid,
+  /// ROS 2 type name, e.g. `std_msgs/msg/String`
+  pub topic_type: String,
+  pub qos: QosPolicies,
+}
+
+impl TopicEndpointInfo {
+  pub(crate) fn new(
+    endpoint: &EndpointDescription,
+    endpoint_kind: EndpointKind,
+    node_name: Option<NodeName>,
+  ) -> TopicEndpointInfo {
+    TopicEndpointInfo {
+      node_name,
+      endpoint_kind,
+      endpoint_gid: Gid::from(endpoint.guid),
+      topic_type: demangle_type_name(&endpoint.type_name),
+      qos: endpoint.qos.clone(),
+    }
+  }
+}
+
+/// Change in the ROS 2 graph.
+///
+/// See [`Node::graph_event_stream`](crate::Node::graph_event_stream).
+///
+/// Endpoint owner `node` is `None`, if the endpoint is not known to belong to
+/// any ROS 2 Node. This may also happen, if DDS Discovery learns of the
+/// endpoint before ROS 2 Discovery learns of its owner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphEvent {
+  NodeAppeared(NodeName),
+  NodeDisappeared(NodeName),
+  PublisherAdded(GraphEndpoint),
+  PublisherRemoved(GraphEndpoint),
+  SubscriptionAdded(GraphEndpoint),
+  SubscriptionRemoved(GraphEndpoint),
+  /// Some Server for this Service appeared.
+  ServiceAvailable {
+    service: String,
+    service_type: String,
+  },
+  /// The last Server for this Service disappeared.
+  ServiceUnavailable {
+    service: String,
+    service_type: String,
+  },
+}
+
+/// Publisher or Subscription in a [`GraphEvent`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEndpoint {
+  /// ROS 2 Topic name
+  pub topic: String,
+  /// ROS 2 type name
+  pub topic_type: String,
+  pub node: Option<NodeName>,
+  pub gid: Gid,
+}
+
+/// State of the ROS 2 graph, as needed for computing [`GraphEvent`]s.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GraphState {
+  nodes: BTreeMap<String, NodeName>, // key is fully qualified name
+  publishers: BTreeMap<Gid, GraphEndpoint>,
+  subscriptions: BTreeMap<Gid, GraphEndpoint>,
+  services: BTreeMap<String, String>, // name -> type, for Services that have a Server
+}
+
+impl GraphState {
+  pub(crate) fn new<'a>(
+    node_infos: &[NodeEntitiesInfo],
+    readers: impl Iterator<Item = &'a EndpointDescription>,
+    writers: impl Iterator<Item = &'a EndpointDescription>,
+  ) -> GraphState {
+    let mut state = GraphState {
+      nodes: node_infos
+        .iter()
+        .map(|info| (info.fully_qualified_name(), info.node_name().clone()))
+        .collect(),
+      ..GraphState::default()
+    };
+
+    for reader in readers {
+      let gid = Gid::from(reader.guid);
+      match demangle_topic_name(&reader.topic_name) {
+        Some((DdsTopicKind::Topic, topic)) => {
+          let node = owner(node_infos, gid, NodeEntitiesInfo::readers);
+          state
+            .subscriptions
+            .insert(gid, graph_endpoint(reader, topic, node));
+        }
+        // Servers read requests
+        Some((DdsTopicKind::ServiceRequest, service)) => {
+          state
+            .services
+            .insert(service, demangle_type_name(&reader.type_name));
+        }
+        _ => {}
+      }
+    }
+    for writer in writers {
+      let gid = Gid::from(writer.guid);
+      if let Some((DdsTopicKind::Topic, topic)) = demangle_topic_name(&writer.topic_name) {
+        let node = owner(node_infos, gid, NodeEntitiesInfo::writers);
+        state
+          .publishers
+          .insert(gid, graph_endpoint(writer, topic, node));
+      }
+    }
+    state
+  }
+
+  /// Events that lead from `self` to `new`.
+  pub(crate) fn diff(&self, new: &GraphState) -> Vec<GraphEvent> {
+    let mut events = Vec::new();
+    events.extend(removed(&new.nodes, &self.nodes).map(|n| GraphEvent::NodeDisappeared(n.clone())));
+    events.extend(removed(&self.nodes, &new.nodes).map(|n| GraphEvent::NodeAppeared(n.clone())));
+    events.extend(
+      removed(&new.publishers, &self.publishers).map(|e| GraphEvent::PublisherRemoved(e.clone())),
+    );
+    events.extend(
+      removed(&self.publishers, &new.publishers).map(|e| GraphEvent::PublisherAdded(e.clone())),
+    );
+    events.extend(
+      removed(&new.subscriptions, &self.subscriptions)
+        .map(|e| GraphEvent::SubscriptionRemoved(e.clone())),
+    );
+    events.extend(
+      removed(&self.subscriptions, &new.subscriptions)
+        .map(|e| GraphEvent::SubscriptionAdded(e.clone())),
+    );
+    for (service, service_type) in self.services.iter() {
+      if !new.services.contains_key(service) {
+        events.push(GraphEvent::ServiceUnavailable {
+          service: service.clone(),
+          service_type: service_type.clone(),
+        });
+      }
+    }
+    for (service, service_type) in new.services.iter() {
+      if !self.services.contains_key(service) {
+        events.push(GraphEvent::ServiceAvailable {
+          service: service.clone(),
+          service_type: service_type.clone(),
+        });
+      }
+    }
+    events
+  }
+}
+
+// Values in `old` whose keys are not in `new`
+fn removed<'a, K: Ord, V>(
+  new: &'a BTreeMap<K, V>,
+  old: &'a BTreeMap<K, V>,
+) -> impl Iterator<Item = &'a V> {
+  old
+    .iter()
+    .filter(move |(k, _)| !new.contains_key(k))
+    .map(|(_, v)| v)
+}
+
+fn owner(
+  node_infos: &[NodeEntitiesInfo],
+  gid: Gid,
+  endpoints: impl Fn(&NodeEntitiesInfo) -> &[Gid],
+) -> Option<NodeName> {
+  node_infos
+    .iter()
+    .find(|info| endpoints(info).contains(&gid))
+    .map(|info| info.node_name().clone())
+}
+
+fn graph_endpoint(
+  endpoint: &EndpointDescription,
+  topic: String,
+  node: Option<NodeName>,
+) -> GraphEndpoint {
+  GraphEndpoint {
+    topic,
+    topic_type: demangle_type_name(&endpoint.type_name),
+    node,
+    gid: Gid::from(endpoint.guid),
+  }
+}
+
+/// Snapshot of the ROS 2 graph: Nodes, and the Topics and Services that
+/// connect them, similar to what `rqt_graph` shows.
+///
+/// See [`Node::graph_snapshot`](crate::Node::graph_snapshot). Nodes are
+/// identified by their fully qualified names. Endpoints that do not belong to
+/// any known ROS 2 Node are left out of the connection lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+  pub nodes: Vec<String>,
+  pub topics: Vec<TopicSnapshot>,
+  pub services: Vec<ServiceSnapshot>,
+}
+
+/// Topic in a [`GraphSnapshot`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicSnapshot {
+  pub name: String,
+  pub types: Vec<String>,
+  /// Nodes publishing to this Topic
+  pub publishers: Vec<String>,
+  /// Nodes subscribing to this Topic
+  pub subscribers: Vec<String>,
+}
+
+/// Service in a [`GraphSnapshot`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceSnapshot {
+  pub name: String,
+  pub types: Vec<String>,
+  /// Nodes serving this Service
+  pub servers: Vec<String>,
+  /// Nodes calling this Service
+  pub clients: Vec<String>,
+}
+
+impl GraphSnapshot {
+  pub(crate) fn new<'a>(
+    node_infos: &[NodeEntitiesInfo],
+    readers: impl Iterator<Item = &'a EndpointDescription>,
+    writers: impl Iterator<Item = &'a EndpointDescription>,
+  ) -> GraphSnapshot {
+    // name -> (types, publishers or servers, subscribers or clients)
+    type Connections = BTreeMap<String, (Vec<String>, BTreeSet<String>, BTreeSet<String>)>;
+    let mut topics = Connections::new();
+    let mut services = Connections::new();
+
+    let endpoints = readers
+      .map(|e| (e, false))
+      .chain(writers.map(|e| (e, true)));
+    for (endpoint, is_writer) in endpoints {
+      let (kind, name) = match demangle_topic_name(&endpoint.topic_name) {
+        Some(kind_and_name) => kind_and_name,
+        None => continue,
+      };
+      let gid = Gid::from(endpoint.guid);
+      let node = if is_writer {
+        owner(node_infos, gid, NodeEntitiesInfo::writers)
+      } else {
+        owner(node_infos, gid, NodeEntitiesInfo::readers)
+      };
+      // Servers read requests and write replies. Clients do the opposite.
+      let (map, first) = match kind {
+        DdsTopicKind::Topic => (&mut topics, is_writer),
+        DdsTopicKind::ServiceRequest => (&mut services, !is_writer),
+        DdsTopicKind::ServiceReply => (&mut services, is_writer),
+      };
+      let (types, firsts, seconds) = map.entry(name).or_default();
+      let ros_type = demangle_type_name(&endpoint.type_name);
+      if !types.contains(&ros_type) {
+        types.push(ros_type);
+      }
+      if let Some(node) = node {
+        let node = node.fully_qualified_name();
+        if first {
+          firsts.insert(node);
+        } else {
+          seconds.insert(node);
+        }
+      }
+    }
+
+    let mut nodes: Vec<String> = node_infos
+      .iter()
+      .map(NodeEntitiesInfo::fully_qualified_name)
+      .collect();
+    nodes.sort();
+    nodes.dedup();
+
+    GraphSnapshot {
+      nodes,
+      topics: topics
+        .into_iter()
+        .map(|(name, (types, publishers, subscribers))| TopicSnapshot {
+          name,
+          types,
+          publishers: publishers.into_iter().collect(),
+          subscribers: subscribers.into_iter().collect(),
+        })
+        .collect(),
+      services: services
+        .into_iter()
+        .map(|(name, (types, servers, clients))| ServiceSnapshot {
+          name,
+          types,
+          servers: servers.into_iter().collect(),
+          clients: clients.into_iter().collect(),
+        })
+        .collect(),
+    }
+  }
+
+  /// Render as a [Graphviz](https://graphviz.org/) `dot` graph.
+  ///
+  /// Nodes are ellipses, Topics boxes, and Services diamonds. Edges point in
+  /// the direction of data: from publisher to Topic to subscriber, and from
+  /// client to Service to server.
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph ros_graph {\n  rankdir=LR;\n");
+    // Writing to a String cannot fail.
+    for node in &self.nodes {
+      writeln!(dot, "  {} [shape=ellipse];", dot_id(node)).unwrap();
+    }
+    for topic in &self.topics {
+      let id = dot_id(&format!("topic:{}", topic.name));
+      let label = dot_id(&format!("{}\\n{}", topic.name, topic.types.join("\\n")));
+      writeln!(dot, "  {id} [shape=box, label={label}];").unwrap();
+      for node in &topic.publishers {
+        writeln!(dot, "  {} -> {id};", dot_id(node)).unwrap();
+      }
+      for node in &topic.subscribers {
+        writeln!(dot, "  {id} -> {};", dot_id(node)).unwrap();
+      }
+    }
+    for service in &self.services {
+      let id = dot_id(&format!("service:{}", service.name));
+      let label = dot_id(&format!("{}\\n{}", service.name, service.types.join("\\n")));
+      writeln!(dot, "  {id} [shape=diamond, label={label}];").unwrap();
+      for node in &service.clients {
+        writeln!(dot, "  {} -> {id};", dot_id(node)).unwrap();
+      }
+      for node in &service.servers {
+        writeln!(dot, "  {id} -> {};", dot_id(node)).unwrap();
+      }
+    }
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+// Quoted dot identifier
+fn dot_id(s: &str) -> String {
+  format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+/// What a DDS Topic is used for in ROS 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DdsTopicKind {
+  Topic,
+  ServiceRequest,
+  ServiceReply,
+}
+
+/// Translate a DDS Topic name to a ROS 2 Topic or Service name.
+///
+/// Returns `None` if the DDS Topic is not a ROS 2 Topic or Service, e.g.
+/// `ros_discovery_info` or DDS built-in topics.
+pub(crate) fn demangle_topic_name(dds_name: &str) -> Option<(DdsTopicKind, String)> {
+  let (kind, name) = if let Some(name) = dds_name.strip_prefix("rt/") {
+    (DdsTopicKind::Topic, name)
+  } else if let Some(name) = dds_name.strip_prefix("rq/") {
+    (DdsTopicKind::ServiceRequest, name.strip_suffix("Request")?)
+  } else if let Some(name) = dds_name.strip_prefix("rr/") {
+    (DdsTopicKind::ServiceReply, name.strip_suffix("Reply")?)
+  } else {
+    return None;
+  };
+  if name.is_empty() {
+    None
+  } else {
+    Some((kind, format!("/{name}")))
+  }
+}
+
+/// Translate a DDS type name to a ROS 2 type name, e.g.
+/// `std_msgs::msg::dds_::String_` to `std_msgs/msg/String`.
+///
+/// Service Request and Response types are translated to the Service type,
+/// e.g. `example_interfaces::srv::dds_::AddTwoInts_Request_` to
+/// `example_interfaces/srv/AddTwoInts`. Names not following the ROS 2
+/// convention are returned unchanged.
+pub(crate) fn demangle_type_name(dds_type: &str) -> String {
+  let parts: Vec<&str> = dds_type.split("::").collect();
+  match parts.as_slice() {
+    [package, interface, "dds_", type_name] => {
+      let mut type_name = type_name.strip_suffix('_').unwrap_or(type_name);
+      if *interface != "msg" {
+        // Services and Actions
+        type_name = type_name
+          .strip_suffix("_Request")
+          .or_else(|| type_name.strip_suffix("_Response"))
+          .unwrap_or(type_name);
+      }
+      format!("{package}/{interface}/{type_name}")
+    }
+    _ => dds_type.to_owned(),
+  }
+}
+
+/// Is the name hidden, i.e. does any of its tokens start with an underscore?
+///
+/// E.g. the Services and Topics that implement an Action are hidden.
+pub(crate) fn is_hidden_name(name: &str) -> bool {
+  name.split('/').any(|token| token.starts_with('_'))
+}
+
+const ACTION_SEND_GOAL: &str = "/_action/send_goal";
+
+/// If the Service is the `send_goal` Service of an Action, return Action name
+/// and type.
+///
+/// Input names are ROS 2 names, i.e. already demangled.
+pub(crate) fn action_name_and_type(
+  service_name: &str,
+  service_type: &str,
+) -> Option<(String, String)> {
+  let action_name = service_name.strip_suffix(ACTION_SEND_GOAL)?;
+  let action_type = service_type.strip_suffix("_SendGoal")?;
+  Some((action_name.to_owned(), action_type.to_owned()))
+}
+
+// Add a name and type, avoiding duplicate types
+pub(crate) fn insert_name_and_type(
+  names_and_types: &mut NamesAndTypes,
+  name: String,
+  ros_type: String,
+) {
+  let types = names_and_types.entry(name).or_default();
+  if !types.contains(&ros_type) {
+    types.push(ros_type);
+  }
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_demangle() {
+  assert_eq!(
+    demangle_topic_name("rt/ns/chatter"),
+    Some((DdsTopicKind::Topic, "/ns/chatter".to_owned()))
+  );
+  assert_eq!(
+    demangle_topic_name("rq/add_two_intsRequest"),
+    Some((DdsTopicKind::ServiceRequest, "/add_two_ints".to_owned()))
+  );
+  assert_eq!(
+    demangle_topic_name("rr/add_two_intsReply"),
+    Some((DdsTopicKind::ServiceReply, "/add_two_ints".to_owned()))
+  );
+  assert_eq!(demangle_topic_name("ros_discovery_info"), None);
+  assert_eq!(demangle_topic_name("rq/no_suffix"), None);
+
+  assert_eq!(
+    demangle_type_name("std_msgs::msg::dds_::String_"),
+    "std_msgs/msg/String"
+  );
+  assert_eq!(
+    demangle_type_name("example_interfaces::srv::dds_::AddTwoInts_Response_"),
+    "example_interfaces/srv/AddTwoInts"
+  );
+  assert_eq!(demangle_type_name("SomeDdsType"), "SomeDdsType");
+
+  let send_goal_type = demangle_type_name("ex::action::dds_::Fibonacci_SendGoal_Request_");
+  assert_eq!(
+    action_name_and_type("/fib/_action/send_goal", &send_goal_type),
+    Some(("/fib".to_owned(), "ex/action/Fibonacci".to_owned()))
+  );
+
+  assert!(is_hidden_name("/fib/_action/send_goal"));
+  assert!(is_hidden_name("/_private/topic"));
+  assert!(!is_hidden_name("/add_two_ints"));
+  assert!(!is_hidden_name("/ns/not_hidden_"));
+}
+
+#[test]
+fn test_graph_state_diff() {
+  let endpoint = |id: u8, topic: &str| EndpointDescription {
+    updated_time: chrono::Utc::now(),
+    guid: rustdds::GUID::from_bytes([id; 16]),
+    topic_name: topic.to_owned(),
+    type_name: "std_msgs::msg::dds_::String_".to_owned(),
+    qos: QosPolicies::qos_none(),
+  };
+  let node_name = NodeName::new("/", "talker").unwrap();
+  let mut node = NodeEntitiesInfo::new(node_name.clone());
+  node.add_writer(Gid::from(rustdds::GUID::from_bytes([1; 16])));
+
+  let empty = GraphState::default();
+  let writers = [endpoint(1, "rt/chatter")];
+  let readers = [endpoint(2, "rq/add_two_intsRequest")];
+  let state = GraphState::new(&[node], readers.iter(), writers.iter());
+
+  let events = empty.diff(&state);
+  assert_eq!(events.len(), 3);
+  assert_eq!(events[0], GraphEvent::NodeAppeared(node_name.clone()));
+  assert!(matches!(&events[1],
+    GraphEvent::PublisherAdded(GraphEndpoint { topic, node: Some(n), .. })
+      if topic == "/chatter" && *n == node_name));
+  assert!(matches!(&events[2],
+    GraphEvent::ServiceAvailable { service, .. } if service == "/add_two_ints"));
+
+  let events = state.diff(&empty);
+  assert_eq!(events.len(), 3);
+  assert_eq!(events[0], GraphEvent::NodeDisappeared(node_name));
+  assert!(state.diff(&state).is_empty());
+}
+
+#[test]
+fn test_graph_snapshot() {
+  let endpoint = |id: u8, topic: &str, dds_type: &str| EndpointDescription {
+    updated_time: chrono::Utc::now(),
+    guid: rustdds::GUID::from_bytes([id; 16]),
+    topic_name: topic.to_owned(),
+    type_name: dds_type.to_owned(),
+    qos: QosPolicies::qos_none(),
+  };
+  let gid = |id: u8| Gid::from(rustdds::GUID::from_bytes([id; 16]));
+  let mut talker = NodeEntitiesInfo::new(NodeName::new("/", "talker").unwrap());
+  talker.add_writer(gid(1));
+  talker.add_reader(gid(3));
+  talker.add_writer(gid(4));
+  let mut listener = NodeEntitiesInfo::new(NodeName::new("/", "listener").unwrap());
+  listener.add_reader(gid(2));
+
+  let string = "std_msgs::msg::dds_::String_";
+  let writers = [
+    endpoint(1, "rt/chatter", string),
+    endpoint(
+      4,
+      "rr/add_two_intsReply",
+      "example_interfaces::srv::dds_::AddTwoInts_Response_",
+    ),
+  ];
+  let readers = [
+    endpoint(2, "rt/chatter", string),
+    endpoint(
+      3,
+      "rq/add_two_intsRequest",
+      "example_interfaces::srv::dds_::AddTwoInts_Request_",
+    ),
+    endpoint(5, "rt/chatter", string), // not a ROS Node
+  ];
+  let snapshot = GraphSnapshot::new(&[talker, listener], readers.iter(), writers.iter());
+
+  assert_eq!(snapshot.nodes, vec!["/listener", "/talker"]);
+  assert_eq!(
+    snapshot.topics,
+    vec![TopicSnapshot {
+      name: "/chatter".to_owned(),
+      types: vec!["std_msgs/msg/String".to_owned()],
+      publishers: vec!["/talker".to_owned()],
+      subscribers: vec!["/listener".to_owned()],
+    }]
+  );
+  assert_eq!(
+    snapshot.services,
+    vec![ServiceSnapshot {
+      name: "/add_two_ints".to_owned(),
+      types: vec!["example_interfaces/srv/AddTwoInts".to_owned()],
+      servers: vec!["/talker".to_owned()],
+      clients: vec![],
+    }]
+  );
+
+  let dot = snapshot.to_dot();
+  assert!(dot.starts_with("digraph ros_graph {\n"));
+  assert!(dot.contains("  \"/talker\" -> \"topic:/chatter\";\n"));
+  assert!(dot.contains("  \"topic:/chatter\" -> \"/listener\";\n"));
+  assert!(dot.contains("  \"service:/add_two_ints\" -> \"/talker\";\n"));
+}
@@ -72,18 +72,26 @@ pub mod interfaces;
 
 /// ROS 2 Action machinery
 pub mod action;
+pub mod arguments;
+pub mod dynamic;
 pub mod entities_info;
 mod gid;
+pub mod graph;
 pub mod log;
 pub mod message;
 pub mod message_info;
 pub mod names;
+pub mod parameter_client;
+pub mod parameter_events;
+pub mod parameter_file;
 pub mod parameters;
 #[doc(hidden)]
 pub mod pubsub;
 pub mod rcl_interfaces;
 pub mod ros_time;
 pub mod service;
+pub mod statistics;
+pub mod statistics_msgs;
 
 pub mod steady_time;
 mod wide_string;
@@ -101,9 +109,13 @@ pub use names::{ActionTypeName, MessageTypeName, Name, NodeName, ServiceTypeName
 #[doc(inline)]
 pub use message_info::MessageInfo;
 #[doc(inline)]
+pub use gid::Gid;
+#[doc(inline)]
 pub use node::*;
 #[doc(inline)]
-pub use parameters::{Parameter, ParameterValue};
+pub use parameter_client::ParameterClient;
+#[doc(inline)]
+pub use parameters::{Parameter, ParameterChange, ParameterValue};
 #[doc(inline)]
 pub use pubsub::*;
 #[doc(inline)]
@@ -117,7 +129,10 @@ pub use ros_time::{ROSTime, SystemTime};
 
 /// Module for stuff we do not want to export from top level;
 pub mod ros2 {
-  pub use rustdds::{qos::policy, Duration, QosPolicies, QosPolicyBuilder, Timestamp};
+  pub use rustdds::{
+    qos::policy, rpc::SampleIdentity, Duration, QosPolicies, QosPolicyBuilder,
+    RepresentationIdentifier, Timestamp, WriteOptions, WriteOptionsBuilder,
+  };
   //TODO: re-export RustDDS error types until ros2-client defines its own
   pub use rustdds::dds::{CreateError, ReadError, WaitError, WriteError};
 
@@ -1,6 +1,8 @@
 //! Metadata for received `Message`s, such as `Timestamp`s and publisher id.
 use rustdds::{rpc::SampleIdentity, *};
 
+use crate::gid::Gid;
+
 /// Message metadata
 #[derive(Debug, Clone)]
 pub struct MessageInfo {
@@ -12,6 +14,13 @@ pub struct MessageInfo {
 }
 
 impl MessageInfo {
+  /// Time when the message was received by the local DDS reader, i.e. before
+  /// it was taken by the application.
+  ///
+  /// A `MessageInfo` converted from a DDS `SampleInfo` has the time of the
+  /// conversion instead, because `SampleInfo` does not record the reception
+  /// time. `MessageInfo`s given by [`Subscription`](crate::Subscription) have
+  /// the reception time.
   pub fn received_timestamp(&self) -> Timestamp {
     self.received_timestamp
   }
@@ -24,6 +33,17 @@ impl MessageInfo {
     self.publisher
   }
 
+  /// Gid of the publisher, as in ROS 2 `rmw_message_info_t`
+  pub fn publisher_gid(&self) -> Gid {
+    self.publisher.into()
+  }
+
+  /// Sequence number of the message within the publisher. These are
+  /// consecutive, so a gap indicates lost messages.
+  pub fn sequence_number(&self) -> SequenceNumber {
+    self.sequence_number
+  }
+
   pub fn sample_identity(&self) -> rustdds::rpc::SampleIdentity {
     rustdds::rpc::SampleIdentity {
       writer_guid: self.writer_guid(),
@@ -39,7 +59,9 @@ impl MessageInfo {
 impl From<&SampleInfo> for MessageInfo {
   fn from(sample_info: &SampleInfo) -> MessageInfo {
     MessageInfo {
-      received_timestamp: Timestamp::ZERO, // TODO!
+      // SampleInfo does not tell the reception time, so the best we can do is
+      // the time when the sample was read.
+      received_timestamp: Timestamp::now(),
       source_timestamp: sample_info.source_timestamp(),
       sequence_number: sample_info.sample_identity().sequence_number,
       publisher: sample_info.publication_handle(), // DDS has an odd name for this
@@ -51,7 +73,7 @@ impl From<&SampleInfo> for MessageInfo {
 impl<M> From<&rustdds::no_key::DeserializedCacheChange<M>> for MessageInfo {
   fn from(dcc: &rustdds::no_key::DeserializedCacheChange<M>) -> MessageInfo {
     MessageInfo {
-      received_timestamp: Timestamp::ZERO, // TODO!
+      received_timestamp: dcc.receive_instant,
       source_timestamp: dcc.source_timestamp(),
       sequence_number: dcc.sequence_number,
       publisher: dcc.writer_guid(),
@@ -59,3 +81,31 @@ impl<M> From<&rustdds::no_key::DeserializedCacheChange<M>> for MessageInfo {
     }
   }
 }
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_message_info_from_cache_change() {
+  let writer = GUID::from_bytes([3; 16]);
+  let source_timestamp = Timestamp::now();
+  let dcc = rustdds::no_key::DeserializedCacheChange {
+    receive_instant: Timestamp::now(),
+    writer_guid: writer,
+    sequence_number: SequenceNumber::from(42),
+    write_options: WriteOptionsBuilder::new()
+      .source_timestamp(source_timestamp)
+      .build(),
+    sample: (),
+  };
+  let info = MessageInfo::from(&dcc);
+
+  assert!(info.received_timestamp() != Timestamp::ZERO);
+  assert!(info.received_timestamp() >= source_timestamp);
+  assert_eq!(info.received_timestamp(), dcc.receive_instant);
+  assert_eq!(info.source_timestamp(), Some(source_timestamp));
+  assert_eq!(info.sequence_number(), dcc.sequence_number);
+  assert_eq!(info.writer_guid(), writer);
+  assert_eq!(info.publisher_gid(), Gid::from(writer));
+  assert_eq!(info.sample_identity().sequence_number, dcc.sequence_number);
+}
@@ -6,6 +6,9 @@
 
 use std::fmt;
 
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
 // TODO:
 // Conform fully to https://design.ros2.org/articles/topic_and_service_names.html
 // and
@@ -100,6 +103,7 @@ pub enum NameError {
   Empty,
   BadChar(char),
   BadSlash(String, String),
+  BadSubstitution(String),
 }
 
 impl fmt::Display for NameError {
@@ -111,6 +115,9 @@ impl fmt::Display for NameError {
         f,
         "Invalid placement of seprator slashes. namespace={ns}  name={n}"
       ),
+      NameError::BadSubstitution(s) => {
+        write!(f, "Unknown or unbalanced substitution {{{s}}}")
+      }
     }
   }
 }
@@ -129,7 +136,53 @@ pub struct Name {
   absolute: bool,    // in string format, absolute names begin with a slash
 }
 
-// TODO: We do not (yet) support tilde-expansion or brace-substitutions.
+// Substitutions that may appear inside braces in a Name, e.g. "{node}/cmd"
+const SUBSTITUTIONS: [&str; 3] = ["node", "ns", "namespace"];
+
+// Check a single token (path component) of a Name.
+// Tilde "~" is accepted only as the first token of a relative Name.
+fn check_name_token(tok: &str, may_be_tilde: bool) -> Result<(), NameError> {
+  if tok == "~" {
+    return if may_be_tilde {
+      Ok(())
+    } else {
+      Err(NameError::BadChar('~'))
+    };
+  }
+
+  let mut chars = tok.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '{' => {
+        let mut substitution = String::new();
+        loop {
+          match chars.next() {
+            Some('}') => break,
+            Some(c) => substitution.push(c),
+            None => return Err(NameError::BadSubstitution(substitution)), // unbalanced
+          }
+        }
+        if !SUBSTITUTIONS.contains(&substitution.as_str()) {
+          return Err(NameError::BadSubstitution(substitution));
+        }
+      }
+      c if c.is_ascii_alphanumeric() || c == '_' => { /* ok */ }
+      bad => return Err(NameError::BadChar(bad)),
+    }
+  }
+
+  match tok.chars().next() {
+    None => Err(NameError::Empty),
+    Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '{' => {
+      if tok.contains("__") {
+        Err(NameError::BadChar('_'))
+      } else {
+        Ok(())
+      }
+    }
+    Some(bad) => Err(NameError::BadChar(bad)),
+  }
+}
 
 impl Name {
   /// Construct a new `Name` from namespace and base name.
@@ -142,9 +195,10 @@ impl Name {
   /// Do not put slashes in the `base_name`.
   /// Base name is not allowed to be empty, but the namespace may be empty.
   ///
-  /// Tilde or brace substitutions are not (yet) supported.
+  /// A relative Name may begin with the private namespace token `~`, e.g.
+  /// namespace `"~"`. Tokens may contain substitutions `{node}`, `{ns}`, or
+  /// `{namespace}`. These are expanded when the Name is used by a Node.
   pub fn new(namespace: &str, base_name: &str) -> Result<Name, NameError> {
-    // TODO: Implement all of the checks here
     let (namespace_rel, absolute) = if let Some(rel) = namespace.strip_prefix('/') {
       (rel, true)
     } else {
@@ -155,22 +209,6 @@ impl Name {
       return Err(NameError::Empty);
     }
 
-    let ok_start_char = |c: char| c.is_ascii_alphabetic() || c == '_';
-    let no_multi_underscore = |s: &str| !s.contains("__");
-
-    if let Some(bad) = base_name
-      .chars()
-      .find(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
-    {
-      return Err(NameError::BadChar(bad));
-    } else if !base_name.starts_with(ok_start_char) {
-      return Err(NameError::BadChar(base_name.chars().next().unwrap_or('?')));
-    } else if !no_multi_underscore(base_name) {
-      return Err(NameError::BadChar('_'));
-    } else {
-      // ok
-    }
-
     let preceeding_tokens = if namespace_rel.is_empty() {
       // If the namespace is "" or "/", we want [] instead of [""]
       Vec::new()
@@ -190,14 +228,10 @@ impl Name {
       ));
     }
 
-    if preceeding_tokens.iter().all(|tok| {
-      tok.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
-        && tok.starts_with(ok_start_char)
-        && no_multi_underscore(tok)
-    }) { /* ok */
-    } else {
-      return Err(NameError::BadChar('?')); //TODO. Find which char is bad.
+    for (i, tok) in preceeding_tokens.iter().enumerate() {
+      check_name_token(tok, i == 0 && !absolute)?;
     }
+    check_name_token(base_name, preceeding_tokens.is_empty() && !absolute)?;
 
     Ok(Name {
       base_name: base_name.to_owned(),
@@ -236,24 +270,102 @@ impl Name {
     }
   }
 
-  pub fn to_dds_name(&self, kind_prefix: &str, node: &NodeName, suffix: &str) -> String {
+  /// Expand the private namespace `~` and substitutions `{node}`, `{ns}`,
+  /// and `{namespace}` using the name of `node`.
+  ///
+  /// A Name that begins with `~` becomes absolute. Fails if the result is not
+  /// a valid Name, e.g. `x{ns}` in the root namespace expands to `x/`.
+  pub fn expand(&self, node: &NodeName) -> Result<Name, NameError> {
+    let needs_expansion = self
+      .preceeding_tokens
+      .iter()
+      .chain(std::iter::once(&self.base_name))
+      .any(|tok| tok == "~" || tok.contains('{'));
+    if !needs_expansion {
+      return Ok(self.clone());
+    }
+
+    let mut expanded = self.to_string();
+    if expanded == "~" {
+      expanded = node.fully_qualified_name();
+    } else if let Some(rest) = expanded.strip_prefix("~/") {
+      expanded = node.fully_qualified_name() + "/" + rest;
+    }
+    expanded = expanded
+      .replace("{node}", node.base_name())
+      .replace("{namespace}", node.namespace())
+      .replace("{ns}", node.namespace());
+    // Namespaces begin with a slash, which may now be doubled.
+    while expanded.contains("//") {
+      expanded = expanded.replace("//", "/");
+    }
+
+    Name::parse(&expanded)
+  }
+
+  pub fn to_dds_name(
+    &self,
+    kind_prefix: &str,
+    node: &NodeName,
+    suffix: &str,
+  ) -> Result<String, NameError> {
+    let expanded = self.expand(node)?;
     let mut result = kind_prefix.to_owned();
     assert!(!result.ends_with('/')); // "rt"
-    if self.absolute {
+    if expanded.absolute {
       // absolute name: do not add node namespace
-    } else {
+    } else if node.namespace() != "/" {
       // relative name: Prefix with Node namespace
       result.push_str(node.namespace()); // "rt/node_ns"
     }
     result.push('/'); // "rt/node_ns/" or "rt/"
-    self.preceeding_tokens.iter().for_each(|tok| {
+    expanded.preceeding_tokens.iter().for_each(|tok| {
       result.push_str(tok);
       result.push('/');
     });
     // rt/node_ns/prec_tok1/
-    result.push_str(&self.base_name);
+    result.push_str(&expanded.base_name);
     result.push_str(suffix);
-    result
+    Ok(result)
+  }
+
+  /// Convert to an absolute Name, i.e. prefix a relative Name with the
+  /// namespace of `node`.
+  pub fn to_absolute(&self, node: &NodeName) -> Result<Name, NameError> {
+    let expanded = self.expand(node)?;
+    if expanded.absolute {
+      Ok(expanded)
+    } else {
+      let mut preceeding_tokens: Vec<String> = node
+        .namespace()
+        .split('/')
+        .filter(|tok| !tok.is_empty())
+        .map(str::to_owned)
+        .collect();
+      preceeding_tokens.extend(expanded.preceeding_tokens);
+      Ok(Name {
+        base_name: expanded.base_name,
+        preceeding_tokens,
+        absolute: true,
+      })
+    }
+  }
+
+  /// Apply name remapping rules `(from, to)`.
+  ///
+  /// Both this Name and the rules are first expanded and made absolute with
+  /// respect to `node`. The first matching rule is applied. If no rule matches, the
+  /// result is the absolute form of this Name.
+  ///
+  /// See [Remapping Names](https://design.ros2.org/articles/static_remapping.html)
+  pub fn remap(&self, node: &NodeName, rules: &[(Name, Name)]) -> Result<Name, NameError> {
+    let absolute = self.to_absolute(node)?;
+    for (from, to) in rules {
+      if from.to_absolute(node)? == absolute {
+        return to.to_absolute(node);
+      }
+    }
+    Ok(absolute)
   }
 
   pub(crate) fn push(&self, new_suffix: &str) -> Name {
@@ -469,6 +581,117 @@ fn test_name_parse() {
     Name::new("/a/b/c", "nn").unwrap()
   );
 
-  assert_eq!(Name::parse("a/nn").unwrap().is_absolute(), false);
-  assert_eq!(Name::parse("/a/nn").unwrap().is_absolute(), true);
+  assert!(!Name::parse("a/nn").unwrap().is_absolute());
+  assert!(Name::parse("/a/nn").unwrap().is_absolute());
+}
+
+#[test]
+fn test_name_remap() {
+  let node = NodeName::new("/ns", "node").unwrap();
+  let rules = vec![
+    (
+      Name::parse("chatter").unwrap(),
+      Name::parse("talk").unwrap(),
+    ),
+    (
+      Name::parse("/abs/topic").unwrap(),
+      Name::parse("/other").unwrap(),
+    ),
+  ];
+
+  assert_eq!(
+    Name::parse("chatter").unwrap().to_absolute(&node).unwrap(),
+    Name::parse("/ns/chatter").unwrap()
+  );
+  assert_eq!(
+    Name::parse("chatter")
+      .unwrap()
+      .remap(&node, &rules)
+      .unwrap(),
+    Name::parse("/ns/talk").unwrap()
+  );
+  // Rules are made absolute before comparison, too.
+  assert_eq!(
+    Name::parse("/ns/chatter")
+      .unwrap()
+      .remap(&node, &rules)
+      .unwrap(),
+    Name::parse("/ns/talk").unwrap()
+  );
+  assert_eq!(
+    Name::parse("abs/topic")
+      .unwrap()
+      .remap(&node, &rules)
+      .unwrap(),
+    Name::parse("/ns/abs/topic").unwrap()
+  );
+  assert_eq!(
+    Name::parse("/abs/topic")
+      .unwrap()
+      .remap(&node, &rules)
+      .unwrap(),
+    Name::parse("/other").unwrap()
+  );
+  assert_eq!(
+    Name::parse("/other")
+      .unwrap()
+      .remap(&node, &rules)
+      .unwrap()
+      .to_dds_name("rt", &node, "")
+      .unwrap(),
+    "rt/other"
+  );
+}
+
+#[test]
+fn test_name_expand() {
+  assert!(Name::parse("~").is_ok());
+  assert!(Name::parse("~/status").is_ok());
+  assert!(Name::parse("{node}/cmd").is_ok());
+  assert!(Name::parse("a/{ns}_x/b").is_ok());
+  assert!(Name::parse("~foo").is_err()); // must separate tilde with a slash
+  assert!(Name::parse("a/~/b").is_err()); // tilde only at the beginning
+  assert!(Name::parse("/~/b").is_err()); // absolute name cannot be private
+  assert!(Name::parse("{node/cmd").is_err()); // unbalanced
+  assert!(Name::parse("{bogus}/cmd").is_err()); // unknown substitution
+
+  let node = NodeName::new("/ns", "node").unwrap();
+  let root_node = NodeName::new("/", "node").unwrap();
+
+  assert_eq!(
+    Name::parse("~/status").unwrap().expand(&node).unwrap(),
+    Name::parse("/ns/node/status").unwrap()
+  );
+  assert_eq!(
+    Name::parse("~").unwrap().expand(&root_node).unwrap(),
+    Name::parse("/node").unwrap()
+  );
+  assert_eq!(
+    Name::parse("{node}/cmd").unwrap().expand(&node).unwrap(),
+    Name::parse("node/cmd").unwrap()
+  );
+  assert_eq!(
+    Name::parse("{ns}/cmd").unwrap().expand(&root_node).unwrap(),
+    Name::parse("/cmd").unwrap()
+  );
+  assert_eq!(
+    Name::parse("~/status")
+      .unwrap()
+      .to_dds_name("rt", &node, "")
+      .unwrap(),
+    "rt/ns/node/status"
+  );
+  assert_eq!(
+    Name::parse("{node}/cmd")
+      .unwrap()
+      .to_dds_name("rq", &node, "Request")
+      .unwrap(),
+    "rq/ns/node/cmdRequest"
+  );
+  // Substitution must not leave an invalid name behind
+  assert!(Name::parse("x{ns}").unwrap().expand(&root_node).is_err());
+  assert!(Name::parse("x{ns}")
+    .unwrap()
+    .to_dds_name("rt", &root_node, "")
+    .is_err());
 }
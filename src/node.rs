@@ -2,16 +2,16 @@ use std::{
   collections::{BTreeMap, BTreeSet},
   error::Error,
   fmt,
+  path::{Path, PathBuf},
   pin::Pin,
   sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
+    Arc, Mutex, Weak,
   },
 };
 
 use futures::{
-  pin_mut, stream::FusedStream, task, task::Poll, Future, FutureExt, Stream, StreamExt,
-  stream,
+  future, pin_mut, stream::FusedStream, task, task::Poll, Future, FutureExt, Stream, StreamExt,
 };
 use async_channel::Receiver;
 #[allow(unused_imports)]
@@ -24,15 +24,25 @@ use rustdds::{
 
 use crate::{
   action::*,
+  arguments::RosArguments,
   builtin_interfaces,
   context::{Context, DEFAULT_SUBSCRIPTION_QOS},
+  dynamic::{DynamicPublisher, DynamicSubscription, MessageDefinition},
   entities_info::{NodeEntitiesInfo, ParticipantEntitiesInfo},
   gid::Gid,
+  graph,
+  graph::{
+    DdsTopicKind, EndpointKind, GraphEvent, GraphSnapshot, GraphState, NamesAndTypes,
+    TopicEndpointInfo,
+  },
   log as ros_log,
-  log::Log,
+  log::{Log, LogLevel},
   names::*,
+  parameter_client::ParameterClient,
+  parameter_events::ParameterEventSubscription,
+  parameter_file::ParameterFile,
   parameters::*,
-  pubsub::{Publisher, Subscription},
+  pubsub::{Publisher, RawSubscription, Subscription},
   rcl_interfaces,
   ros_time::ROSTime,
   service::{Client, Server, Service, ServiceMapping},
@@ -48,17 +58,20 @@ type ParameterFunc = dyn Fn(&str, &ParameterValue) -> SetParametersResult + Send
 /// they ae always needed and have no reasonable default.
 #[must_use]
 pub struct NodeOptions {
-  #[allow(dead_code)]
   cli_args: Vec<String>,
-  #[allow(dead_code)]
   use_global_arguments: bool, // process-wide command line args
-  enable_rosout: bool, // use rosout topic for logging?
+  enable_rosout: bool,        // use rosout topic for logging?
   enable_rosout_reading: bool,
   start_parameter_services: bool,
   declared_parameters: Vec<Parameter>,
+  parameter_descriptors: Vec<ParameterDescriptor>,
   allow_undeclared_parameters: bool,
   parameter_validator: Option<Box<ParameterFunc>>,
   parameter_set_action: Option<Box<ParameterFunc>>,
+  name_remapping: Vec<(Name, Name)>,
+  params_files: Vec<PathBuf>,
+  parameter_service_mapping: Option<ServiceMapping>,
+  parameter_service_qos: Option<QosPolicies>,
 }
 
 impl NodeOptions {
@@ -73,9 +86,14 @@ impl NodeOptions {
       enable_rosout_reading: false,
       start_parameter_services: true,
       declared_parameters: Vec::new(),
+      parameter_descriptors: Vec::new(),
       allow_undeclared_parameters: false,
       parameter_validator: None,
       parameter_set_action: None,
+      name_remapping: Vec::new(),
+      params_files: Vec::new(),
+      parameter_service_mapping: None,
+      parameter_service_qos: None,
     }
   }
   pub fn enable_rosout(self, enable_rosout: bool) -> NodeOptions {
@@ -92,6 +110,58 @@ impl NodeOptions {
     }
   }
 
+  /// Command line arguments specific to this Node.
+  ///
+  /// Only arguments inside a `--ros-args` section are used, e.g.
+  /// `["--ros-args", "-r", "__node:=other_name"]`. These take precedence over
+  /// process-wide arguments. See [`arguments`](crate::arguments) for details.
+  pub fn arguments(self, cli_args: Vec<String>) -> NodeOptions {
+    NodeOptions { cli_args, ..self }
+  }
+
+  /// Add a topic or service name remapping rule `from:=to`.
+  ///
+  /// These rules are tried in the order given, and before any `-r` rules from
+  /// the command line.
+  pub fn remap_name(mut self, from: Name, to: Name) -> NodeOptions {
+    self.name_remapping.push((from, to));
+    self
+  }
+
+  /// Load parameter values from a ROS 2 parameter (YAML) file.
+  ///
+  /// Parameters in the file that match the Node name replace declared values
+  /// when the Node is created. Undeclared parameters are ignored, unless
+  /// undeclared parameters are allowed. Files given here are read before any
+  /// `--params-file` files from the command line. See
+  /// [`parameter_file`](crate::parameter_file) for details.
+  pub fn params_file(mut self, path: impl AsRef<Path>) -> NodeOptions {
+    self.params_files.push(path.as_ref().to_path_buf());
+    self
+  }
+
+  /// Should the process command line, i.e. [`std::env::args()`], be used?
+  /// Default is `true`.
+  pub fn use_global_arguments(self, use_global_arguments: bool) -> NodeOptions {
+    NodeOptions {
+      use_global_arguments,
+      ..self
+    }
+  }
+
+  // Parse node-local and (optionally) global command line arguments.
+  fn ros_arguments(&self) -> Result<RosArguments, NodeCreateError> {
+    let local = RosArguments::parse(&self.cli_args)
+      .map_err(|e| NodeCreateError::BadArgument(e.to_string()))?;
+    if self.use_global_arguments {
+      let global =
+        RosArguments::from_env().map_err(|e| NodeCreateError::BadArgument(e.to_string()))?;
+      Ok(local.with_global(global))
+    } else {
+      Ok(local)
+    }
+  }
+
   pub fn declare_parameter(mut self, name: &str, value: ParameterValue) -> NodeOptions {
     self.declared_parameters.push(Parameter {
       name: name.to_owned(),
@@ -101,6 +171,41 @@ impl NodeOptions {
     self
   }
 
+  /// Declare a parameter with constraints given in a [`ParameterDescriptor`].
+  ///
+  /// The descriptor is checked whenever the parameter is set, locally or
+  /// remotely, and is reported by the DescribeParameters service. Parameters
+  /// declared without a descriptor may not change type.
+  pub fn declare_parameter_with_descriptor(
+    mut self,
+    value: ParameterValue,
+    descriptor: ParameterDescriptor,
+  ) -> NodeOptions {
+    self.declared_parameters.push(Parameter {
+      name: descriptor.name.clone(),
+      value,
+    });
+    self.parameter_descriptors.push(descriptor);
+    self
+  }
+
+  /// ServiceMapping for Parameter Services. Default is
+  /// [`Context::service_mapping`].
+  pub fn parameter_service_mapping(self, service_mapping: ServiceMapping) -> NodeOptions {
+    NodeOptions {
+      parameter_service_mapping: Some(service_mapping),
+      ..self
+    }
+  }
+
+  /// QoS policies for Parameter Services, both requests and responses.
+  pub fn parameter_service_qos(self, qos: QosPolicies) -> NodeOptions {
+    NodeOptions {
+      parameter_service_qos: Some(qos),
+      ..self
+    }
+  }
+
   pub fn parameter_validator(mut self, validator: Box<ParameterFunc>) -> NodeOptions {
     self.parameter_validator = Some(validator);
     self
@@ -136,6 +241,17 @@ struct ParameterServers {
   describe_parameters_server: Server<rcl_interfaces::DescribeParametersService>,
 }
 
+// Default QoS for both ends of Parameter Services
+//TODO: Check QoS policies against ROS 2 specs or some refernce.
+fn parameter_service_qos() -> QosPolicies {
+  QosPolicyBuilder::new()
+    .reliability(policy::Reliability::Reliable {
+      max_blocking_time: Duration::from_millis(100),
+    })
+    .history(policy::History::KeepLast { depth: 1 })
+    .build()
+}
+
 // ----------------------------------------------------------------------------------------------------
 // ----------------------------------------------------------------------------------------------------
 /// Spinner implements Node's background event loop.
@@ -147,12 +263,16 @@ pub struct Spinner {
   ros_context: Context,
   stop_spin_receiver: async_channel::Receiver<()>,
 
-  readers_to_remote_writers: Arc<Mutex<BTreeMap<GUID, BTreeSet<GUID>>>>,
-  writers_to_remote_readers: Arc<Mutex<BTreeMap<GUID, BTreeSet<GUID>>>>,
+  readers_to_remote_writers: Arc<Mutex<MatchedEndpoints>>,
+  writers_to_remote_readers: Arc<Mutex<MatchedEndpoints>>,
   // Keep track of ros_discovery_info
   external_nodes: Arc<Mutex<BTreeMap<Gid, Vec<NodeEntitiesInfo>>>>,
+  // All Readers and Writers seen in DDS Discovery, including local ones
+  discovered_readers: Arc<Mutex<BTreeMap<GUID, EndpointDescription>>>,
+  discovered_writers: Arc<Mutex<BTreeMap<GUID, EndpointDescription>>>,
   //suppress_node_info_updates: Arc<AtomicBool>, // temporarily suppress sending updates
   status_event_senders: Arc<Mutex<Vec<async_channel::Sender<NodeEvent>>>>,
+  graph_event_senders: Arc<Mutex<Vec<async_channel::Sender<GraphEvent>>>>,
 
   use_sim_time: Arc<AtomicBool>,
   sim_time: Arc<Mutex<ROSTime>>,
@@ -162,6 +282,8 @@ pub struct Spinner {
   parameter_servers: Option<ParameterServers>,
   parameter_events_writer: Arc<Publisher<raw::ParameterEvent>>,
   parameters: Arc<Mutex<BTreeMap<String, ParameterValue>>>,
+  parameter_descriptors: Arc<Mutex<BTreeMap<String, ParameterDescriptor>>>,
+  parameter_change_senders: Arc<Mutex<Vec<async_channel::Sender<ParameterChange>>>>,
   parameter_validator: Option<Arc<Mutex<Box<ParameterFunc>>>>,
   parameter_set_action: Option<Arc<Mutex<Box<ParameterFunc>>>>,
   fully_qualified_node_name: String,
@@ -178,7 +300,27 @@ where
 }
 
 impl Spinner {
-  pub async fn spin(self) -> CreateResult<()> {
+  /// Run the event loop, until the Node is dropped.
+  ///
+  /// Waiting for matches is possible from the call of `spin()` until the
+  /// returned Future completes or is dropped.
+  pub fn spin(self) -> impl Future<Output = CreateResult<()>> {
+    // Mark running already here, and not on the first poll, so that waiting
+    // right after spawning the Future does not fail.
+    self
+      .readers_to_remote_writers
+      .lock()
+      .unwrap()
+      .spinner_started();
+    self
+      .writers_to_remote_readers
+      .lock()
+      .unwrap()
+      .spinner_started();
+    self.run()
+  }
+
+  async fn run(self) -> CreateResult<()> {
     let dds_status_listener = self.ros_context.domain_participant().status_listener();
     let dds_status_stream = dds_status_listener.as_async_status_stream();
     pin_mut!(dds_status_stream);
@@ -196,6 +338,9 @@ impl Spinner {
     let ros_clock_stream = ros_clock_reader.async_stream();
     pin_mut!(ros_clock_stream);
 
+    // Previous state, for detecting changes
+    let mut graph_state = GraphState::default();
+
     // These are Option< impl Stream<_>>
     let mut get_parameters_stream_opt = self
       .parameter_servers
@@ -313,23 +458,19 @@ impl Spinner {
         set_parameters_atomically_request = next_if_some(&mut set_parameters_atomically_stream_opt).fuse() => {
           match set_parameters_atomically_request {
             Ok( (req_id, req) ) => {
-              warn!("Set parameters atomically request {req:?}");
-              let results =
-                req.parameter.iter()
-                  .cloned()
-                  .map( Parameter::from ) // convert from "raw::Parameter"
-                  .map( |Parameter{ .. } |
-                      // TODO: Implement atomic setting.
-                      Err("Setting parameters atomically is not implemented.".to_owned())
-                    )
-                  .map(|r| r.into()) // to "raw" Result for serialization
-                  .collect();
-              warn!("Set parameters atomically response: {results:?}");
+              info!("Set parameters atomically request {req:?}");
+              let result =
+                self.set_parameters_atomically(
+                  req.parameter.into_iter()
+                    .map( Parameter::from ) // convert from "raw::Parameter"
+                    .collect()
+                ).into(); // to "raw" Result for serialization
+              info!("Set parameters atomically response: {result:?}");
               // .unwrap() below should be safe, as we would not be here if the Server did not exist
               self.parameter_servers.as_ref().unwrap().set_parameters_atomically_server
-                .async_send_response(req_id, rcl_interfaces::SetParametersAtomicallyResponse{ results })
+                .async_send_response(req_id, rcl_interfaces::SetParametersAtomicallyResponse{ result })
                 .await
-                .unwrap_or_else(|e| warn!("SetParameters response error {e:?}"));
+                .unwrap_or_else(|e| warn!("SetParametersAtomically response error {e:?}"));
             }
             Err(e) => warn!("SetParametersAtomically request error {e:?}"),
           }
@@ -372,11 +513,16 @@ impl Spinner {
               info!("Describe parameters request {req:?}");
               let values = {
                 let parameters = self.parameters.lock().unwrap();
+                let descriptors = self.parameter_descriptors.lock().unwrap();
                 req.names.iter()
                   .map( |name|
                     {
-                      if let Some(value) = parameters.get(name) {
-                        ParameterDescriptor::from_value(name, value)
+                      if let Some(descriptor) = descriptors.get(name) {
+                        descriptor.clone()
+                      } else if let Some(value) = parameters.get(name) {
+                        // not explicitly declared, so there are no constraints
+                        ParameterDescriptor{ dynamic_typing: true,
+                          .. ParameterDescriptor::from_value(name, value) }
                       } else {
                         ParameterDescriptor::unknown(name)
                       }
@@ -402,8 +548,11 @@ impl Spinner {
               // insert to Node-local ros_discovery_info bookkeeping
               let mut info_map = self.external_nodes.lock().unwrap();
               info_map.insert( part_update.gid, part_update.node_entities_info_seq.clone());
+              drop(info_map);
+              self.update_match_owners();
               // also notify any status listeneners
               self.send_status_event( &NodeEvent::ROS(part_update) );
+              self.update_graph_state(&mut graph_state);
             }
             Err(e) => {
               warn!("ros_discovery_info error {e:?}");
@@ -415,33 +564,46 @@ impl Spinner {
           //println!("{:?}", dp_status_event );
 
           // update remote reader/writer databases
+          let mut graph_changed = true;
           match dp_status_event {
             DomainParticipantStatusEvent::RemoteReaderMatched { local_writer, remote_reader } => {
+              let node = self.node_of_endpoint(remote_reader);
               self.writers_to_remote_readers.lock().unwrap()
-                .entry(local_writer)
-                .and_modify(|s| {s.insert(remote_reader);} )
-                .or_insert(BTreeSet::from([remote_reader]));
+                .insert(local_writer, remote_reader, node);
+              graph_changed = false;
             }
             DomainParticipantStatusEvent::RemoteWriterMatched { local_reader, remote_writer } => {
+              let node = self.node_of_endpoint(remote_writer);
               self.readers_to_remote_writers.lock().unwrap()
-                .entry(local_reader)
-                .and_modify(|s| {s.insert(remote_writer);} )
-                .or_insert(BTreeSet::from([remote_writer]));
+                .insert(local_reader, remote_writer, node);
+              graph_changed = false;
+            }
+            DomainParticipantStatusEvent::ReaderDetected { ref reader } => {
+              graph_changed = self.endpoint_detected(&self.discovered_readers, reader);
+            }
+            DomainParticipantStatusEvent::WriterDetected { ref writer } => {
+              graph_changed = self.endpoint_detected(&self.discovered_writers, writer);
             }
             DomainParticipantStatusEvent::ReaderLost {guid, ..} => {
-              for ( _local, readers)
-              in self.writers_to_remote_readers.lock().unwrap().iter_mut() {
-                readers.remove(&guid);
-              }
+              self.unmatch_remote(guid);
+              self.discovered_readers.lock().unwrap().remove(&guid);
             }
             DomainParticipantStatusEvent::WriterLost {guid, ..} => {
-              for ( _local, writers)
-              in self.readers_to_remote_writers.lock().unwrap().iter_mut() {
-                writers.remove(&guid);
-              }
+              self.unmatch_remote(guid);
+              self.discovered_writers.lock().unwrap().remove(&guid);
+            }
+            DomainParticipantStatusEvent::ParticipantLost {id, ..} => {
+              // Only the GuidPrefix part identifies the Participant.
+              self.participant_lost(GUID {
+                prefix: id,
+                ..GUID::GUID_UNKNOWN
+              });
             }
 
-            _ => {}
+            _ => graph_changed = false,
+          }
+          if graph_changed {
+            self.update_graph_state(&mut graph_state);
           }
 
           // also notify any status listeneners
@@ -480,8 +642,119 @@ impl Spinner {
     }
   }
 
+  // Which ROS Node has the remote Reader or Writer, if known yet
+  fn node_of_endpoint(&self, guid: GUID) -> Option<NodeName> {
+    node_of_endpoint(&self.external_nodes, &self.ros_context, guid)
+  }
+
+  // Remote endpoint is gone, so it is no longer matched to any local one.
+  fn unmatch_remote(&self, remote: GUID) {
+    self
+      .readers_to_remote_writers
+      .lock()
+      .unwrap()
+      .remove_remote(remote);
+    self
+      .writers_to_remote_readers
+      .lock()
+      .unwrap()
+      .remove_remote(remote);
+  }
+
+  // ros_discovery_info may name the owners of already matched endpoints.
+  fn update_match_owners(&self) {
+    let owner_of = |remote| self.node_of_endpoint(remote);
+    self
+      .readers_to_remote_writers
+      .lock()
+      .unwrap()
+      .update_owners(owner_of);
+    self
+      .writers_to_remote_readers
+      .lock()
+      .unwrap()
+      .update_owners(owner_of);
+  }
+
+  // Reader or Writer seen in DDS Discovery. A passive observer leaves its own
+  // endpoints out of the graph. Returns true, if the graph changed.
+  fn endpoint_detected(
+    &self,
+    endpoints: &Mutex<BTreeMap<GUID, EndpointDescription>>,
+    endpoint: &EndpointDescription,
+  ) -> bool {
+    if self.ros_context.is_passive_observer()
+      && endpoint.guid.prefix == self.ros_context.domain_participant().guid().prefix
+    {
+      return false;
+    }
+    endpoints
+      .lock()
+      .unwrap()
+      .insert(endpoint.guid, endpoint.clone());
+    true
+  }
+
+  // A remote DomainParticipant is gone.
+  fn participant_lost(&self, participant: GUID) {
+    let id = participant.prefix;
+    // Endpoints of the lost Participant are gone also
+    let mut lost_remotes = self.readers_to_remote_writers.lock().unwrap().remotes();
+    lost_remotes.extend(self.writers_to_remote_readers.lock().unwrap().remotes());
+    for remote in lost_remotes.into_iter().filter(|guid| guid.prefix == id) {
+      self.unmatch_remote(remote);
+    }
+    self
+      .discovered_readers
+      .lock()
+      .unwrap()
+      .retain(|guid, _| guid.prefix != id);
+    self
+      .discovered_writers
+      .lock()
+      .unwrap()
+      .retain(|guid, _| guid.prefix != id);
+    // and so are its ROS Nodes. There will be no more ros_discovery_info
+    // updates from it, so notify as if it had sent an empty update.
+    let mut lost_participants = Vec::new();
+    self.external_nodes.lock().unwrap().retain(|gid, _| {
+      let lost = GUID::from(*gid).prefix == id;
+      if lost {
+        lost_participants.push(*gid);
+      }
+      !lost
+    });
+    for gid in lost_participants {
+      self.send_status_event(&NodeEvent::ROS(ParticipantEntitiesInfo::new(
+        gid,
+        Vec::new(),
+      )));
+    }
+  }
+
+  // Compare current graph to the previous state, and notify
+  // graph_event_stream() listeners.
+  fn update_graph_state(&self, graph_state: &mut GraphState) {
+    let new_state = GraphState::new(
+      &node_entities_infos(&self.external_nodes, &self.ros_context),
+      self.discovered_readers.lock().unwrap().values(),
+      self.discovered_writers.lock().unwrap().values(),
+    );
+    send_to_all(&self.graph_event_senders, graph_state.diff(&new_state));
+    *graph_state = new_state;
+  }
+
   // Keep this function in sync with the same function in Node.
-  fn validate_parameter_on_set(&self, name: &str, value: &ParameterValue) -> SetParametersResult {
+  fn validate_parameter_on_set(
+    &self,
+    name: &str,
+    current: Option<&ParameterValue>,
+    value: &ParameterValue,
+  ) -> SetParametersResult {
+    // declared constraints
+    if let Some(descriptor) = self.parameter_descriptors.lock().unwrap().get(name) {
+      descriptor.validate(current, value)?;
+    }
     match name {
       // built-in parameter check
       "use_sim_time" => match value {
@@ -523,9 +796,10 @@ impl Spinner {
 
   /// Sets a parameter value. Parameter must be declared before setting.
   pub fn set_parameter(&self, name: &str, value: ParameterValue) -> Result<(), String> {
-    let already_set = self.parameters.lock().unwrap().contains_key(name);
+    let current = self.parameters.lock().unwrap().get(name).cloned();
+    let already_set = current.is_some();
     if self.allow_undeclared_parameters || already_set {
-      self.validate_parameter_on_set(name, &value)?;
+      self.validate_parameter_on_set(name, current.as_ref(), &value)?;
       self.execute_parameter_set_actions(name, &value)?;
 
       // no errors, prepare for sending notificaiton
@@ -544,7 +818,15 @@ impl Spinner {
         .parameters
         .lock()
         .unwrap()
-        .insert(name.to_owned(), value);
+        .insert(name.to_owned(), value.clone());
+      send_to_all(
+        &self.parameter_change_senders,
+        vec![ParameterChange {
+          name: name.to_owned(),
+          old: current,
+          new: value,
+        }],
+      );
       // and notify
       self
         .parameter_events_writer
@@ -561,8 +843,162 @@ impl Spinner {
       Err("Setting undeclared parameter '".to_owned() + name + "' is not allowed.")
     }
   }
+
+  /// Sets several parameter values as a single operation.
+  ///
+  /// Either all of the parameters are set, or none of them is. All parameters
+  /// must be declared before setting, unless undeclared parameters are
+  /// allowed. A single `ParameterEvent` is published for the whole set.
+  ///
+  /// All parameters are validated before any set action is executed. If a
+  /// set action still fails, the actions already executed are undone by
+  /// executing them again with the previous values.
+  ///
+  /// The parameters are kept locked from validation until the new values are
+  /// stored, so that concurrent sets cannot get in between. Validators and set
+  /// actions must not access the parameters of this Node.
+  pub fn set_parameters_atomically(&self, parameters: Vec<Parameter>) -> SetParametersResult {
+    let mut param_db = self.parameters.lock().unwrap();
+    let current: Vec<Option<ParameterValue>> = parameters
+      .iter()
+      .map(|Parameter { name, .. }| param_db.get(name).cloned())
+      .collect();
+
+    // Check everything before touching anything
+    for (Parameter { name, value }, current) in parameters.iter().zip(&current) {
+      if !(self.allow_undeclared_parameters || current.is_some()) {
+        return Err("Setting undeclared parameter '".to_owned() + name + "' is not allowed.");
+      }
+      self
+        .validate_parameter_on_set(name, current.as_ref(), value)
+        .map_err(|e| format!("Parameter '{name}': {e}"))?;
+    }
+
+    for (i, Parameter { name, value }) in parameters.iter().enumerate() {
+      if let Err(e) = self.execute_parameter_set_actions(name, value) {
+        // Undo the actions already executed by executing them again with the
+        // current values. Parameters that had no value cannot be undone.
+        for (Parameter { name, .. }, current) in parameters[..i].iter().zip(&current).rev() {
+          if let Some(current) = current {
+            self
+              .execute_parameter_set_actions(name, current)
+              .unwrap_or_else(|e| warn!("set_parameters_atomically: rollback of {name}: {e}"));
+          }
+        }
+        return Err(format!("Parameter '{name}': {e}"));
+      }
+    }
+
+    // no errors, prepare for sending notificaiton
+    let mut new_parameters = Vec::new();
+    let mut changed_parameters = Vec::new();
+    let mut changes = Vec::new();
+    for (Parameter { name, value }, current) in parameters.into_iter().zip(current) {
+      let p = raw::Parameter {
+        name: name.clone(),
+        value: value.clone().into(),
+      };
+      if current.is_some() {
+        changed_parameters.push(p);
+      } else {
+        new_parameters.push(p);
+      }
+      // actually set the parameter
+      param_db.insert(name.clone(), value.clone());
+      changes.push(ParameterChange {
+        name,
+        old: current,
+        new: value,
+      });
+    }
+    drop(param_db);
+    send_to_all(&self.parameter_change_senders, changes);
+
+    // and notify
+    self
+      .parameter_events_writer
+      .publish(raw::ParameterEvent {
+        timestamp: rustdds::Timestamp::now(), // differs from version in Node!!!
+        node: self.fully_qualified_node_name.clone(),
+        new_parameters,
+        changed_parameters,
+        deleted_parameters: vec![],
+      })
+      .unwrap_or_else(|e| warn!("set_parameters_atomically: {e:?}"));
+    Ok(())
+  }
 } // impl Spinner
 
+impl Drop for Spinner {
+  fn drop(&mut self) {
+    // Wake up anyone waiting for matches, as they will not be coming.
+    self
+      .readers_to_remote_writers
+      .lock()
+      .unwrap()
+      .spinner_stopped();
+    self
+      .writers_to_remote_readers
+      .lock()
+      .unwrap()
+      .spinner_stopped();
+  }
+}
+
+// Notify all listeners on unbounded channels, e.g. parameter_change_stream().
+// Listeners that have gone away are removed.
+fn send_to_all<T: Clone>(senders: &Mutex<Vec<async_channel::Sender<T>>>, items: Vec<T>) {
+  let mut senders = senders.lock().unwrap();
+  senders.retain(|sender| !sender.is_closed());
+  for item in items {
+    for sender in senders.iter() {
+      // Channel is unbounded, so it cannot be full.
+      sender
+        .try_send(item.clone())
+        .unwrap_or_else(|_| debug!("send_to_all: Receiver went away"));
+    }
+  }
+}
+
+// Find the ROS Node of a Reader or Writer, using ros_discovery_info
+fn node_of_endpoint(
+  external_nodes: &Mutex<BTreeMap<Gid, Vec<NodeEntitiesInfo>>>,
+  context: &Context,
+  guid: GUID,
+) -> Option<NodeName> {
+  let gid = Gid::from(guid);
+  node_entities_infos(external_nodes, context)
+    .into_iter()
+    .find(|info| info.readers().contains(&gid) || info.writers().contains(&gid))
+    .map(|info| info.node_name().clone())
+}
+
+// Local Nodes and those learned from ros_discovery_info. A passive observer
+// does not count its own Nodes, as it does not announce them.
+fn node_entities_infos(
+  external_nodes: &Mutex<BTreeMap<Gid, Vec<NodeEntitiesInfo>>>,
+  context: &Context,
+) -> Vec<NodeEntitiesInfo> {
+  let mut infos: Vec<NodeEntitiesInfo> = external_nodes
+    .lock()
+    .unwrap()
+    .values()
+    .flatten()
+    .cloned()
+    .collect();
+  if context.is_passive_observer() {
+    return infos;
+  }
+  let local_nodes = context.participant_entities_info();
+  for local in local_nodes.nodes() {
+    // Our own ros_discovery_info may have been received also
+    if !infos.contains(local) {
+      infos.push(local.clone());
+    }
+  }
+  infos
+}
+
 // ----------------------------------------------------------------------------------------------------
 // ----------------------------------------------------------------------------------------------------
 
@@ -571,6 +1007,7 @@ impl Spinner {
 pub enum NodeCreateError {
   DDS(CreateError),
   BadParameter(String),
+  BadArgument(String),
 }
 
 impl From<CreateError> for NodeCreateError {
@@ -584,6 +1021,7 @@ impl fmt::Display for NodeCreateError {
     match self {
       Self::DDS(create_error) => write!(f, "NodeCreateError::DDS : {create_error}"),
       Self::BadParameter(s) => write!(f, "NodeCreateError::BadParameter : {s}"),
+      Self::BadArgument(s) => write!(f, "NodeCreateError::BadArgument : {s}"),
     }
   }
 }
@@ -592,11 +1030,33 @@ impl Error for NodeCreateError {
   fn source(&self) -> Option<&(dyn Error + 'static)> {
     match self {
       Self::DDS(create_error) => Some(create_error),
-      Self::BadParameter(_) => None,
+      Self::BadParameter(_) | Self::BadArgument(_) => None,
+    }
+  }
+}
+
+/// Error when waiting for discovery events, e.g. in
+/// [`Publisher::wait_for_subscription`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryError {
+  /// Discovery events are delivered by the [`Spinner`] of the Node, but it is
+  /// not running (anymore). Call [`Node::spinner`] and run it in an async task.
+  NoSpinner,
+  /// The given time ran out before the wait was over.
+  Timeout,
+}
+
+impl fmt::Display for DiscoveryError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::NoSpinner => write!(f, "DiscoveryError::NoSpinner : Node has no Spinner running"),
+      Self::Timeout => write!(f, "DiscoveryError::Timeout"),
     }
   }
 }
 
+impl Error for DiscoveryError {}
+
 /// Error when setting `Parameter`s
 pub enum ParameterError {
   AlreadyDeclared,
@@ -607,9 +1067,6 @@ pub enum ParameterError {
 /// parameter events topics internally.
 ///
 /// These are produced by a [`Context`].
-
-// TODO: We should notify ROS discovery when readers or writers are removed, but
-// now we do not do that.
 pub struct Node {
   node_name: NodeName,
   options: NodeOptions,
@@ -617,9 +1074,10 @@ pub struct Node {
   pub(crate) ros_context: Context,
 
   // sets of Readers and Writers belonging to ( = created via) this Node
-  // These indicate what has been created locally.
-  readers: BTreeSet<Gid>,
-  writers: BTreeSet<Gid>,
+  // These indicate what has been created locally. Endpoints remove themselves
+  // via EndpointRegistration when dropped.
+  readers: Arc<Mutex<BTreeSet<Gid>>>,
+  writers: Arc<Mutex<BTreeSet<Gid>>>,
 
   suppress_node_info_updates: Arc<AtomicBool>,
   // temporarily suppress sending updates
@@ -628,19 +1086,28 @@ pub struct Node {
   // Keep track of who is matched via DDS Discovery
   // Map keys are lists of local Subscriptions and Publishers.
   // Map values are lists of matched Publishers / Subscriptions.
-  readers_to_remote_writers: Arc<Mutex<BTreeMap<GUID, BTreeSet<GUID>>>>,
-  writers_to_remote_readers: Arc<Mutex<BTreeMap<GUID, BTreeSet<GUID>>>>,
+  readers_to_remote_writers: Arc<Mutex<MatchedEndpoints>>,
+  writers_to_remote_readers: Arc<Mutex<MatchedEndpoints>>,
 
   // Keep track of ros_discovery_info
   external_nodes: Arc<Mutex<BTreeMap<Gid, Vec<NodeEntitiesInfo>>>>,
+  // Keep track of all Readers and Writers in DDS Discovery. Updated by Spinner.
+  discovered_readers: Arc<Mutex<BTreeMap<GUID, EndpointDescription>>>,
+  discovered_writers: Arc<Mutex<BTreeMap<GUID, EndpointDescription>>>,
   stop_spin_sender: Option<async_channel::Sender<()>>,
 
+  // Topic and Service name remapping rules (from, to)
+  name_remapping: Vec<(Name, Name)>,
+
   // Channels to report discovery events to
   status_event_senders: Arc<Mutex<Vec<async_channel::Sender<NodeEvent>>>>,
+  graph_event_senders: Arc<Mutex<Vec<async_channel::Sender<GraphEvent>>>>,
 
   // builtin writers and readers
   rosout_writer: Option<Publisher<Log>>,
   rosout_reader: Option<Subscription<Log>>,
+  // Messages below this level are not written to rosout
+  rosout_level: Option<LogLevel>,
 
   // Parameter events (rcl_interfaces)
   // Parameter Services are inside Spinner
@@ -648,6 +1115,10 @@ pub struct Node {
 
   // Parameter store
   parameters: Arc<Mutex<BTreeMap<String, ParameterValue>>>,
+  // Constraints for explicitly declared parameters
+  parameter_descriptors: Arc<Mutex<BTreeMap<String, ParameterDescriptor>>>,
+  // Listeners of parameter_change_stream()
+  parameter_change_senders: Arc<Mutex<Vec<async_channel::Sender<ParameterChange>>>>,
   // allow_undeclared_parameters: bool, // this is inside "options"
   parameter_validator: Option<Arc<Mutex<Box<ParameterFunc>>>>,
   parameter_set_action: Option<Arc<Mutex<Box<ParameterFunc>>>>,
@@ -666,7 +1137,48 @@ impl Node {
     let paramtopic = ros_context.get_parameter_events_topic();
     let rosout_topic = ros_context.get_rosout_topic();
 
-    let enable_rosout = options.enable_rosout;
+    // Apply command line arguments. Node-specific rules refer to the original
+    // Node name, so resolve everything before renaming.
+    let ros_arguments = options.ros_arguments()?;
+    let mut file_parameters = Vec::new();
+    for path in options
+      .params_files
+      .iter()
+      .chain(ros_arguments.params_files())
+    {
+      let file = ParameterFile::load(path).map_err(|e| {
+        NodeCreateError::BadParameter(format!("Parameter file {}: {e}", path.display()))
+      })?;
+      file_parameters.extend(file.parameters_for(&node_name));
+    }
+    let parameter_overrides = ros_arguments.parameters_for(&node_name);
+    let rosout_level = ros_arguments.log_level_for(&node_name);
+    let mut name_remapping = std::mem::take(&mut options.name_remapping);
+    for rule in ros_arguments.remap_rules() {
+      if rule.from().starts_with("__") || !rule.applies_to(&node_name) {
+        // Special rule, e.g. "__node", or not for us
+        continue;
+      }
+      let from = Name::parse(rule.from());
+      let to = Name::parse(rule.to());
+      match (from, to) {
+        (Ok(from), Ok(to)) => name_remapping.push((from, to)),
+        (Err(e), _) | (_, Err(e)) => {
+          return Err(NodeCreateError::BadArgument(format!(
+            "Remapping rule {}:={} : {e}",
+            rule.from(),
+            rule.to()
+          )))
+        }
+      }
+    }
+    let node_name = ros_arguments
+      .resolve_node_name(&node_name)
+      .map_err(|e| NodeCreateError::BadArgument(format!("Node name remapping: {e}")))?;
+
+    let enable_rosout = ros_arguments
+      .enable_rosout()
+      .unwrap_or(options.enable_rosout);
     let rosout_reader = options.enable_rosout_reading;
 
     let parameter_events_writer = ros_context.create_publisher(&paramtopic, None)?;
@@ -677,12 +1189,23 @@ impl Node {
       name: "use_sim_time".to_string(),
       value: ParameterValue::Boolean(false),
     });
-    let parameters = options
+    // Declared parameters have fixed type, unless a descriptor says otherwise.
+    let mut parameter_descriptors = options
       .declared_parameters
       .iter()
-      .cloned()
-      .map(|Parameter { name, value }| (name, value))
-      .collect::<BTreeMap<String, ParameterValue>>();
+      .map(|Parameter { name, value }| (name.clone(), ParameterDescriptor::from_value(name, value)))
+      .collect::<BTreeMap<String, ParameterDescriptor>>();
+    for descriptor in options.parameter_descriptors.drain(..) {
+      parameter_descriptors.insert(descriptor.name.clone(), descriptor);
+    }
+
+    let parameters = initial_parameters(
+      &options.declared_parameters,
+      &parameter_descriptors,
+      file_parameters,
+      parameter_overrides,
+      options.allow_undeclared_parameters,
+    );
 
     let parameter_validator = options
       .parameter_validator
@@ -697,18 +1220,25 @@ impl Node {
       node_name,
       options,
       ros_context,
-      readers: BTreeSet::new(),
-      writers: BTreeSet::new(),
-      readers_to_remote_writers: Arc::new(Mutex::new(BTreeMap::new())),
-      writers_to_remote_readers: Arc::new(Mutex::new(BTreeMap::new())),
+      readers: Arc::new(Mutex::new(BTreeSet::new())),
+      writers: Arc::new(Mutex::new(BTreeSet::new())),
+      readers_to_remote_writers: Arc::new(Mutex::new(MatchedEndpoints::default())),
+      writers_to_remote_readers: Arc::new(Mutex::new(MatchedEndpoints::default())),
       external_nodes: Arc::new(Mutex::new(BTreeMap::new())),
+      discovered_readers: Arc::new(Mutex::new(BTreeMap::new())),
+      discovered_writers: Arc::new(Mutex::new(BTreeMap::new())),
       suppress_node_info_updates: Arc::new(AtomicBool::new(false)),
       stop_spin_sender: None,
+      name_remapping,
       status_event_senders: Arc::new(Mutex::new(Vec::new())),
+      graph_event_senders: Arc::new(Mutex::new(Vec::new())),
       rosout_writer: None, // Set below
       rosout_reader: None,
+      rosout_level,
       parameter_events_writer: Arc::new(parameter_events_writer),
       parameters: Arc::new(Mutex::new(parameters)),
+      parameter_descriptors: Arc::new(Mutex::new(parameter_descriptors)),
+      parameter_change_senders: Arc::new(Mutex::new(Vec::new())),
       parameter_validator,
       parameter_set_action,
       use_sim_time: Arc::new(AtomicBool::new(false)),
@@ -738,7 +1268,7 @@ impl Node {
       .unwrap()
       .iter()
       .try_for_each(|(name, value)| {
-        node.validate_parameter_on_set(name, value)?;
+        node.validate_parameter_on_set(name, None, value)?;
         node.execute_parameter_set_actions(name, value)?;
         Ok(())
       })
@@ -779,20 +1309,21 @@ impl Node {
     let (stop_spin_sender, stop_spin_receiver) = async_channel::bounded(1);
     self.stop_spin_sender = Some(stop_spin_sender);
 
-    //TODO: Check QoS policies against ROS 2 specs or some refernce.
-    let service_qos = QosPolicyBuilder::new()
-      .reliability(policy::Reliability::Reliable {
-        max_blocking_time: Duration::from_millis(100),
-      })
-      .history(policy::History::KeepLast { depth: 1 })
-      .build();
+    let service_qos = self
+      .options
+      .parameter_service_qos
+      .clone()
+      .unwrap_or_else(parameter_service_qos);
 
     let node_name = self.node_name.fully_qualified_name();
 
     self.suppress_node_info_updates(true);
 
     let parameter_servers = if self.options.start_parameter_services {
-      let service_mapping = ServiceMapping::Enhanced; //TODO: parameterize
+      let service_mapping = self
+        .options
+        .parameter_service_mapping
+        .unwrap_or_else(|| self.ros_context.service_mapping());
       let get_parameters_server = self.create_server(
         service_mapping,
         &Name::new(&node_name, "get_parameters").unwrap(),
@@ -862,13 +1393,18 @@ impl Node {
       readers_to_remote_writers: Arc::clone(&self.readers_to_remote_writers),
       writers_to_remote_readers: Arc::clone(&self.writers_to_remote_readers),
       external_nodes: Arc::clone(&self.external_nodes),
+      discovered_readers: Arc::clone(&self.discovered_readers),
+      discovered_writers: Arc::clone(&self.discovered_writers),
       status_event_senders: Arc::clone(&self.status_event_senders),
+      graph_event_senders: Arc::clone(&self.graph_event_senders),
       use_sim_time: Arc::clone(&self.use_sim_time),
       sim_time: Arc::clone(&self.sim_time),
       clock_topic,
       parameter_servers,
       parameter_events_writer: Arc::clone(&self.parameter_events_writer),
       parameters: Arc::clone(&self.parameters),
+      parameter_descriptors: Arc::clone(&self.parameter_descriptors),
+      parameter_change_senders: Arc::clone(&self.parameter_change_senders),
       allow_undeclared_parameters: self.options.allow_undeclared_parameters,
       parameter_validator: self.parameter_validator.as_ref().map(Arc::clone),
       parameter_set_action: self.parameter_set_action.as_ref().map(Arc::clone),
@@ -893,11 +1429,11 @@ impl Node {
       node_info.add_writer(Gid::from(row.guid()));
     }
 
-    for reader in &self.readers {
+    for reader in self.readers.lock().unwrap().iter() {
       node_info.add_reader(*reader);
     }
 
-    for writer in &self.writers {
+    for writer in self.writers.lock().unwrap().iter() {
       node_info.add_writer(*writer);
     }
 
@@ -915,18 +1451,20 @@ impl Node {
     }
   }
 
-  fn add_reader(&mut self, reader: Gid) {
-    self.readers.insert(reader);
+  fn add_reader(&mut self, reader: Gid) -> EndpointRegistration {
+    self.readers.lock().unwrap().insert(reader);
     if !self.suppress_node_info_updates.load(Ordering::SeqCst) {
       self.ros_context.update_node(self.generate_node_info());
     }
+    EndpointRegistration::new(self, reader, &self.readers)
   }
 
-  fn add_writer(&mut self, writer: Gid) {
-    self.writers.insert(writer);
+  fn add_writer(&mut self, writer: Gid) -> EndpointRegistration {
+    self.writers.lock().unwrap().insert(writer);
     if !self.suppress_node_info_updates.load(Ordering::SeqCst) {
       self.ros_context.update_node(self.generate_node_info());
     }
+    EndpointRegistration::new(self, writer, &self.writers)
   }
 
   pub fn base_name(&self) -> &str {
@@ -945,6 +1483,15 @@ impl Node {
     &self.options
   }
 
+  /// Resolve a topic or service name as this Node sees it.
+  ///
+  /// The result is an absolute Name, where relative names have been expanded
+  /// using the Node namespace, and name remapping rules have been applied.
+  /// Fails if a substitution such as `{ns}` leaves an invalid name.
+  pub fn resolve_name(&self, name: &Name) -> Result<Name, NameError> {
+    name.remap(&self.node_name, &self.name_remapping)
+  }
+
   pub fn domain_id(&self) -> u16 {
     self.ros_context.domain_id()
   }
@@ -954,9 +1501,18 @@ impl Node {
 
   pub fn undeclare_parameter(&self, name: &str) {
     let prev_value = self.parameters.lock().unwrap().remove(name);
+    self.parameter_descriptors.lock().unwrap().remove(name);
 
     if let Some(deleted_param) = prev_value {
       // a parameter was actually undeclared. Let others know.
+      send_to_all(
+        &self.parameter_change_senders,
+        vec![ParameterChange {
+          name: name.to_owned(),
+          old: Some(deleted_param.clone()),
+          new: ParameterValue::NotSet,
+        }],
+      );
       self
         .parameter_events_writer
         .publish(raw::ParameterEvent {
@@ -988,9 +1544,10 @@ impl Node {
   // TODO: Setting Parameter to type NotSet counts as parameter deletion. Maybe
   // that needs special handling? At least for notifications.
   pub fn set_parameter(&self, name: &str, value: ParameterValue) -> Result<(), String> {
-    let already_set = self.parameters.lock().unwrap().contains_key(name);
+    let current = self.parameters.lock().unwrap().get(name).cloned();
+    let already_set = current.is_some();
     if self.options.allow_undeclared_parameters || already_set {
-      self.validate_parameter_on_set(name, &value)?;
+      self.validate_parameter_on_set(name, current.as_ref(), &value)?;
       self.execute_parameter_set_actions(name, &value)?;
 
       // no errors, prepare for sending notificaiton
@@ -1009,7 +1566,15 @@ impl Node {
         .parameters
         .lock()
         .unwrap()
-        .insert(name.to_owned(), value);
+        .insert(name.to_owned(), value.clone());
+      send_to_all(
+        &self.parameter_change_senders,
+        vec![ParameterChange {
+          name: name.to_owned(),
+          old: current,
+          new: value,
+        }],
+      );
       // and notify
       self
         .parameter_events_writer
@@ -1027,6 +1592,18 @@ impl Node {
     }
   }
 
+  /// Get an async Stream of Parameter value changes.
+  ///
+  /// Changes are reported after they have been validated and applied, whether
+  /// they were made locally by [`Node::set_parameter`] or remotely via
+  /// Parameter Services. Remote changes require a running
+  /// [`Spinner`]. Each call returns an independent Stream.
+  pub fn parameter_change_stream(&self) -> Receiver<ParameterChange> {
+    let (sender, receiver) = async_channel::unbounded();
+    self.parameter_change_senders.lock().unwrap().push(sender);
+    receiver
+  }
+
   pub fn allow_undeclared_parameters(&self) -> bool {
     self.options.allow_undeclared_parameters
   }
@@ -1052,12 +1629,19 @@ impl Node {
   }
 
   // Keep this function in sync with the same function in Spinner.
-  // TODO: This should refuse to change parameter type, unless
-  // there is a ParamaterDescription defined and it allows
-  // changing type.
+  // `current` is None when the parameter is being initialized.
   // TODO: Setting Parameter to type NotSet counts as parameter deletion. Maybe
   // that needs special handling?
-  fn validate_parameter_on_set(&self, name: &str, value: &ParameterValue) -> SetParametersResult {
+  fn validate_parameter_on_set(
+    &self,
+    name: &str,
+    current: Option<&ParameterValue>,
+    value: &ParameterValue,
+  ) -> SetParametersResult {
+    // declared constraints
+    if let Some(descriptor) = self.parameter_descriptors.lock().unwrap().get(name) {
+      descriptor.validate(current, value)?;
+    }
     match name {
       // built-in parameter check
       "use_sim_time" => match value {
@@ -1098,108 +1682,322 @@ impl Node {
   }
 
   // ///////////////////////////////////////////////////
+  // ROS 2 Graph introspection
+  //
+  // These are based on discovery data collected by the Spinner, so they only
+  // give complete results when a Spinner has been running for a while.
+
+  /// Names of all known Nodes, including local ones, unless the [`Context`] is
+  /// a passive observer.
+  pub fn get_node_names(&self) -> Vec<NodeName> {
+    let mut names: Vec<NodeName> = self
+      .all_node_entities_infos()
+      .iter()
+      .map(|info| info.node_name().clone())
+      .collect();
+    names.sort_by_key(NodeName::fully_qualified_name);
+    names.dedup();
+    names
+  }
 
-  /// Get an async Receiver for discovery events.
+  /// All Topics, and their types.
   ///
-  /// There must be an async task executing `spin` to get any data.
-  /// This function may panic if there is no Spinner running.
-  pub fn status_receiver(&self) -> Receiver<NodeEvent> {
-    if self.have_spinner() {
-      let (status_event_sender, status_event_receiver) = async_channel::bounded(8);
-      self
-        .status_event_senders
-        .lock()
-        .unwrap()
-        .push(status_event_sender);
-      status_event_receiver
-    } else {
-      panic!("status_receiver() cannot set up a receiver, because no Spinner is running.")
-    }
+  /// Hidden Topics, such as those that implement Actions, are included only
+  /// if `include_hidden` is set. See [`Self::get_service_names_and_types`].
+  pub fn get_topic_names_and_types(&self, include_hidden: bool) -> NamesAndTypes {
+    let topics = names_and_types(self.all_endpoints(), |kind| kind == DdsTopicKind::Topic);
+    without_hidden(topics, include_hidden)
   }
 
-  // reader waits for at least one writer to be present
-  pub(crate) fn wait_for_writer(&self, reader: GUID) -> impl Future<Output = ()> {
-    // TODO: This may contain some synchrnoization hazard
-    let status_receiver = self.status_receiver();
+  /// All Services, and their types.
+  ///
+  /// Hidden Services, such as those that implement Actions, are included
+  /// only if `include_hidden` is set. A name is hidden, if any of its tokens
+  /// starts with an underscore.
+  pub fn get_service_names_and_types(&self, include_hidden: bool) -> NamesAndTypes {
+    let services = names_and_types(self.all_endpoints(), |kind| kind != DdsTopicKind::Topic);
+    without_hidden(services, include_hidden)
+  }
 
-    let already_present = self
-      .readers_to_remote_writers
-      .lock()
-      .unwrap()
-      .get(&reader)
-      .map(|writers| !writers.is_empty()) // there is someone matched
-      .unwrap_or(false); // we do not even know the reader
+  /// All Actions, and their types.
+  pub fn get_action_names_and_types(&self) -> NamesAndTypes {
+    actions_of_services(self.get_service_names_and_types(true))
+  }
 
-    if already_present {
-      WriterWait::Ready
-    } else {
-      WriterWait::Wait {
-        this_reader: reader,
-        status_event_stream: Box::pin(status_receiver),
-      }
-    }
+  /// Topics that the given Node publishes, and their types.
+  pub fn get_publisher_names_and_types_by_node(&self, node: &NodeName) -> NamesAndTypes {
+    let (_readers, writers) = self.endpoints_of_node(node);
+    names_and_types(writers, |kind| kind == DdsTopicKind::Topic)
   }
 
-  pub(crate) fn wait_for_reader(&self, writer: GUID) -> impl Future<Output = ()> {
-    // TODO: This may contain some synchrnoization hazard.
-    let status_receiver = self.status_receiver();
+  /// Topics that the given Node subscribes to, and their types.
+  pub fn get_subscriber_names_and_types_by_node(&self, node: &NodeName) -> NamesAndTypes {
+    let (readers, _writers) = self.endpoints_of_node(node);
+    names_and_types(readers, |kind| kind == DdsTopicKind::Topic)
+  }
 
-    let already_present = self
-      .writers_to_remote_readers
-      .lock()
-      .unwrap()
-      .get(&writer)
-      .map(|readers| !readers.is_empty()) // there is someone matched
-      .unwrap_or(false); // we do not even know who is asking
+  /// Services that the given Node serves, and their types.
+  ///
+  /// See [`Self::get_service_names_and_types`] about `include_hidden`.
+  pub fn get_service_names_and_types_by_node(
+    &self,
+    node: &NodeName,
+    include_hidden: bool,
+  ) -> NamesAndTypes {
+    // Server reads requests
+    let (readers, _writers) = self.endpoints_of_node(node);
+    let services = names_and_types(readers, |kind| kind == DdsTopicKind::ServiceRequest);
+    without_hidden(services, include_hidden)
+  }
 
-    // TODO: Is is possible to miss reader events if they appear after the check
-    // above, but do not somehow end up in the status_receiver stream?
+  /// Services that the given Node is a client of, and their types.
+  ///
+  /// See [`Self::get_service_names_and_types`] about `include_hidden`.
+  pub fn get_client_names_and_types_by_node(
+    &self,
+    node: &NodeName,
+    include_hidden: bool,
+  ) -> NamesAndTypes {
+    // Client writes requests
+    let (_readers, writers) = self.endpoints_of_node(node);
+    let services = names_and_types(writers, |kind| kind == DdsTopicKind::ServiceRequest);
+    without_hidden(services, include_hidden)
+  }
 
-    if already_present {
-      info!("wait_for_reader: Already have matched a reader.");
-      ReaderWait::Ready
-    } else {
-      ReaderWait::Wait {
-        this_writer: writer,
-        status_event_stream: Box::pin(status_receiver),
-      }
-    }
+  /// Actions that the given Node serves, and their types.
+  pub fn get_action_server_names_and_types_by_node(&self, node: &NodeName) -> NamesAndTypes {
+    actions_of_services(self.get_service_names_and_types_by_node(node, true))
   }
 
-  pub(crate) fn get_publisher_count(&self, subscription_guid: GUID) -> usize {
-    self
-      .readers_to_remote_writers
-      .lock()
-      .unwrap()
-      .get(&subscription_guid)
-      .map(BTreeSet::len)
-      .unwrap_or_else(|| {
-        error!("get_publisher_count: Subscriber {subscription_guid:?} not known to node.");
-        0
-      })
+  /// Actions that the given Node is a client of, and their types.
+  pub fn get_action_client_names_and_types_by_node(&self, node: &NodeName) -> NamesAndTypes {
+    actions_of_services(self.get_client_names_and_types_by_node(node, true))
   }
 
-  pub(crate) fn get_subscription_count(&self, publisher_guid: GUID) -> usize {
-    self
-      .writers_to_remote_readers
-      .lock()
-      .unwrap()
-      .get(&publisher_guid)
-      .map(BTreeSet::len)
-      .unwrap_or_else(|| {
-        error!("get_subscription_count: Publisher {publisher_guid:?} not known to node.");
-        0
-      })
+  /// Get an async Stream of changes in the ROS 2 graph.
+  ///
+  /// Events are computed by the [`Spinner`], so it must be running to get any
+  /// events. Each call returns an independent Stream, which starts from the
+  /// current state, i.e. only later changes are reported. Use e.g.
+  /// [`Node::get_node_names`] to get the current state.
+  pub fn graph_event_stream(&self) -> Receiver<GraphEvent> {
+    let (sender, receiver) = async_channel::unbounded();
+    self.graph_event_senders.lock().unwrap().push(sender);
+    receiver
   }
 
-  /// Borrow the Subscription to our ROSOut Reader.
+  /// Snapshot of the current ROS 2 graph, e.g. for exporting it as JSON or
+  /// Graphviz `dot`.
   ///
-  /// Availability depends on Node configuration.
-  pub fn rosout_subscription(&self) -> Option<&Subscription<Log>> {
-    self.rosout_reader.as_ref()
+  /// This is based on discovery data collected by the [`Spinner`].
+  pub fn graph_snapshot(&self) -> GraphSnapshot {
+    GraphSnapshot::new(
+      &self.all_node_entities_infos(),
+      self.discovered_readers.lock().unwrap().values(),
+      self.discovered_writers.lock().unwrap().values(),
+    )
   }
 
-  #[allow(clippy::too_many_arguments)]
+  /// Publishers of the given Topic, including QoS and owning Node.
+  pub fn get_publishers_info_by_topic(&self, topic_name: &Name) -> Vec<TopicEndpointInfo> {
+    self.endpoints_info_by_topic(topic_name, EndpointKind::Publisher)
+  }
+
+  /// Subscriptions to the given Topic, including QoS and owning Node.
+  pub fn get_subscriptions_info_by_topic(&self, topic_name: &Name) -> Vec<TopicEndpointInfo> {
+    self.endpoints_info_by_topic(topic_name, EndpointKind::Subscription)
+  }
+
+  fn endpoints_info_by_topic(
+    &self,
+    topic_name: &Name,
+    kind: EndpointKind,
+  ) -> Vec<TopicEndpointInfo> {
+    let dds_name = match self
+      .resolve_name(topic_name)
+      .and_then(|name| name.to_dds_name("rt", &self.node_name, ""))
+    {
+      Ok(dds_name) => dds_name,
+      Err(e) => {
+        // No Topic can have this name.
+        debug!("endpoints_info_by_topic: Topic name {topic_name}: {e}");
+        return Vec::new();
+      }
+    };
+    let endpoints = match kind {
+      EndpointKind::Publisher => &self.discovered_writers,
+      EndpointKind::Subscription => &self.discovered_readers,
+    };
+    topic_endpoint_infos(
+      &dds_name,
+      kind,
+      endpoints.lock().unwrap().values(),
+      &self.all_node_entities_infos(),
+    )
+  }
+
+  fn all_node_entities_infos(&self) -> Vec<NodeEntitiesInfo> {
+    node_entities_infos(&self.external_nodes, &self.ros_context)
+  }
+
+  // (DDS Topic name, DDS type name) of all known Readers and Writers
+  fn all_endpoints(&self) -> Vec<(String, String)> {
+    // Discovered Topics include our own, and do not tell where they came from,
+    // so a passive observer relies on the endpoints only.
+    let mut endpoints: Vec<(String, String)> = if self.ros_context.is_passive_observer() {
+      Vec::new()
+    } else {
+      self
+        .ros_context
+        .discovered_topics()
+        .iter()
+        .map(|t| (t.topic_name().clone(), t.type_name().clone()))
+        .collect()
+    };
+    for map in [&self.discovered_readers, &self.discovered_writers] {
+      endpoints.extend(
+        map
+          .lock()
+          .unwrap()
+          .values()
+          .map(|e| (e.topic_name.clone(), e.type_name.clone())),
+      );
+    }
+    endpoints
+  }
+
+  // (DDS Topic name, DDS type name) of Readers and Writers of the given Node
+  #[allow(clippy::type_complexity)]
+  fn endpoints_of_node(&self, node: &NodeName) -> (Vec<(String, String)>, Vec<(String, String)>) {
+    let fqn = node.fully_qualified_name();
+    let infos: Vec<NodeEntitiesInfo> = self
+      .all_node_entities_infos()
+      .into_iter()
+      .filter(|info| info.fully_qualified_name() == fqn)
+      .collect();
+    let lookup = |map: &Mutex<BTreeMap<GUID, EndpointDescription>>, gids: &[Gid]| {
+      let map = map.lock().unwrap();
+      gids
+        .iter()
+        .filter_map(|gid| map.get(&GUID::from(*gid)))
+        .map(|e| (e.topic_name.clone(), e.type_name.clone()))
+        .collect::<Vec<_>>()
+    };
+    let readers = infos
+      .iter()
+      .flat_map(|info| lookup(&self.discovered_readers, info.readers()))
+      .collect();
+    let writers = infos
+      .iter()
+      .flat_map(|info| lookup(&self.discovered_writers, info.writers()))
+      .collect();
+    (readers, writers)
+  }
+
+  // ///////////////////////////////////////////////////
+
+  /// Get an async Receiver for discovery events.
+  ///
+  /// There must be an async task executing `spin` to get any data.
+  /// Returns an error, if [`Node::spinner`] has not been called.
+  ///
+  /// When a remote DomainParticipant is lost, its ROS Nodes are reported gone
+  /// by a `NodeEvent::ROS` with an empty Node list.
+  pub fn status_receiver(&self) -> Result<Receiver<NodeEvent>, DiscoveryError> {
+    if self.have_spinner() {
+      let (status_event_sender, status_event_receiver) = async_channel::bounded(8);
+      self
+        .status_event_senders
+        .lock()
+        .unwrap()
+        .push(status_event_sender);
+      Ok(status_event_receiver)
+    } else {
+      Err(DiscoveryError::NoSpinner)
+    }
+  }
+
+  // reader waits for at least one writer to be present
+  pub(crate) fn wait_for_writer(&self, reader: GUID) -> MatchWait {
+    MatchWait::new(self, reader, &self.readers_to_remote_writers)
+  }
+
+  // writer waits for at least one reader to be present
+  pub(crate) fn wait_for_reader(&self, writer: GUID) -> MatchWait {
+    MatchWait::new(self, writer, &self.writers_to_remote_readers)
+  }
+
+  pub(crate) fn get_publisher_count(&self, subscription_guid: GUID) -> usize {
+    self
+      .readers_to_remote_writers
+      .lock()
+      .unwrap()
+      .count(subscription_guid)
+      .unwrap_or_else(|| {
+        error!("get_publisher_count: Subscriber {subscription_guid:?} not known to node.");
+        0
+      })
+  }
+
+  pub(crate) fn get_subscription_count(&self, publisher_guid: GUID) -> usize {
+    self
+      .writers_to_remote_readers
+      .lock()
+      .unwrap()
+      .count(publisher_guid)
+      .unwrap_or_else(|| {
+        error!("get_subscription_count: Publisher {publisher_guid:?} not known to node.");
+        0
+      })
+  }
+
+  pub(crate) fn subscription_matched_events(
+    &self,
+    publisher_guid: GUID,
+  ) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    self.matched_events(publisher_guid, &self.writers_to_remote_readers)
+  }
+
+  pub(crate) fn publisher_matched_events(
+    &self,
+    subscription_guid: GUID,
+  ) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    self.matched_events(subscription_guid, &self.readers_to_remote_writers)
+  }
+
+  fn matched_events(
+    &self,
+    local: GUID,
+    matched: &Mutex<MatchedEndpoints>,
+  ) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    if !self.have_spinner() {
+      return Err(DiscoveryError::NoSpinner);
+    }
+    let mut matched = matched.lock().unwrap();
+    if !matched.spinner_running {
+      // Spinner is not running, so the stream would never get anything.
+      return Err(DiscoveryError::NoSpinner);
+    }
+    let (sender, receiver) = async_channel::unbounded();
+    // Report current matches first. Holding the lock, so that nothing is
+    // missed or reported twice.
+    for remote in matched.matched.get(&local).into_iter().flatten() {
+      sender
+        .try_send(MatchEvent::Matched(matched.endpoint(*remote)))
+        .unwrap_or_else(|_| debug!("matched_events: Receiver went away"));
+    }
+    matched.listeners.entry(local).or_default().push(sender);
+    Ok(receiver)
+  }
+
+  /// Borrow the Subscription to our ROSOut Reader.
+  ///
+  /// Availability depends on Node configuration.
+  pub fn rosout_subscription(&self) -> Option<&Subscription<Log>> {
+    self.rosout_reader.as_ref()
+  }
+
+  #[allow(clippy::too_many_arguments)]
   pub fn rosout_raw(
     &self,
     timestamp: Timestamp,
@@ -1212,6 +2010,9 @@ impl Node {
   ) {
     match &self.rosout_writer {
       None => debug!("Rosout not enabled. msg: {log_msg}"),
+      Some(_) if self.rosout_level.is_some_and(|min_level| level < min_level) => {
+        trace!("Rosout level too low. msg: {log_msg}")
+      }
       Some(writer) => {
         writer
           .publish(ros_log::Log {
@@ -1262,7 +2063,10 @@ impl Node {
     type_name: MessageTypeName,
     qos: &QosPolicies,
   ) -> CreateResult<Topic> {
-    let dds_name = topic_name.to_dds_name("rt", &self.node_name, "");
+    let dds_name = self
+      .resolve_name(topic_name)
+      .and_then(|name| name.to_dds_name("rt", &self.node_name, ""))
+      .map_err(|e| bad_name(topic_name, e))?;
     self.ros_context.create_topic(dds_name, type_name, qos)
   }
 
@@ -1278,8 +2082,8 @@ impl Node {
     topic: &Topic,
     qos: Option<QosPolicies>,
   ) -> CreateResult<Subscription<D>> {
-    let sub = self.ros_context.create_subscription(topic, qos)?;
-    self.add_reader(sub.guid().into());
+    let mut sub = self.ros_context.create_subscription(topic, qos)?;
+    sub.set_registration(self.add_reader(sub.guid().into()));
     Ok(sub)
   }
 
@@ -1296,36 +2100,78 @@ impl Node {
     topic: &Topic,
     qos: Option<QosPolicies>,
   ) -> CreateResult<Publisher<D>> {
-    let p = self.ros_context.create_publisher(topic, qos)?;
-    self.add_writer(p.guid().into());
+    let mut p = self.ros_context.create_publisher(topic, qos)?;
+    p.set_registration(self.add_writer(p.guid().into()));
     Ok(p)
   }
 
+  /// Creates a Subscription that gives messages in serialized form, without
+  /// knowing their type.
+  ///
+  /// Serialized messages can be published with a
+  /// `Publisher<SerializedMessage>`, e.g. to relay a Topic.
+  pub fn create_raw_subscription(
+    &mut self,
+    topic: &Topic,
+    qos: Option<QosPolicies>,
+  ) -> CreateResult<RawSubscription> {
+    let (datareader, registration) = self.create_simpledatareader(topic, qos)?;
+    Ok(RawSubscription::new(datareader, registration))
+  }
+
+  /// Creates a Subscription, whose message type is given at run time.
+  ///
+  /// The `definition` must match the type of `topic`, or received messages
+  /// cannot be decoded.
+  pub fn create_dynamic_subscription(
+    &mut self,
+    topic: &Topic,
+    definition: MessageDefinition,
+    qos: Option<QosPolicies>,
+  ) -> CreateResult<DynamicSubscription> {
+    let subscription = self.create_subscription(topic, qos)?;
+    Ok(DynamicSubscription::new(subscription, definition))
+  }
+
+  /// Creates a Publisher, whose message type is given at run time.
+  ///
+  /// The `definition` must match the type of `topic`, or subscribers
+  /// cannot decode the messages.
+  pub fn create_dynamic_publisher(
+    &mut self,
+    topic: &Topic,
+    definition: MessageDefinition,
+    qos: Option<QosPolicies>,
+  ) -> CreateResult<DynamicPublisher> {
+    let publisher = self.create_publisher(topic, qos)?;
+    Ok(DynamicPublisher::new(publisher, definition))
+  }
+
   pub(crate) fn create_simpledatareader<D, DA>(
     &mut self,
     topic: &Topic,
     qos: Option<QosPolicies>,
-  ) -> CreateResult<no_key::SimpleDataReader<D, DA>>
+  ) -> CreateResult<(no_key::SimpleDataReader<D, DA>, EndpointRegistration)>
   where
     D: 'static,
     DA: rustdds::no_key::DeserializerAdapter<D> + 'static,
   {
     let r = self.ros_context.create_simpledatareader(topic, qos)?;
-    self.add_reader(r.guid().into());
-    Ok(r)
+    let registration = self.add_reader(r.guid().into());
+    Ok((r, registration))
   }
 
   pub(crate) fn create_datawriter<D, SA>(
     &mut self,
     topic: &Topic,
     qos: Option<QosPolicies>,
-  ) -> CreateResult<no_key::DataWriter<D, SA>>
+  ) -> CreateResult<(no_key::DataWriter<D, SA>, EndpointRegistration)>
   where
     SA: rustdds::no_key::SerializerAdapter<D>,
   {
     let w = self.ros_context.create_datawriter(topic, qos)?;
-    self.add_writer(w.guid().into());
-    Ok(w)
+    let registration = self.add_writer(w.guid().into());
+    Ok((w, registration))
   }
 
   /// Creates ROS2 Service Client
@@ -1352,15 +2198,22 @@ impl Node {
     // Where are the suffixes documented?
     // And why "Reply" and not "Response" ?
 
+    let service_name = self
+      .resolve_name(service_name)
+      .map_err(|e| bad_name(service_name, e))?;
     let rq_topic = self.ros_context.domain_participant().create_topic(
-      service_name.to_dds_name("rq", &self.node_name, "Request"),
+      service_name
+        .to_dds_name("rq", &self.node_name, "Request")
+        .map_err(|e| bad_name(&service_name, e))?,
       //rq_name,
       service_type_name.dds_request_type(),
       &request_qos,
       TopicKind::NoKey,
     )?;
     let rs_topic = self.ros_context.domain_participant().create_topic(
-      service_name.to_dds_name("rr", &self.node_name, "Reply"),
+      service_name
+        .to_dds_name("rr", &self.node_name, "Reply")
+        .map_err(|e| bad_name(&service_name, e))?,
       //rs_name,
       service_type_name.dds_response_type(),
       &response_qos,
@@ -1404,15 +2257,22 @@ impl Node {
     // Self::check_name_and_add_prefix("rr/", &(service_name.to_owned() +
     // "Reply"))?;
 
+    let service_name = self
+      .resolve_name(service_name)
+      .map_err(|e| bad_name(service_name, e))?;
     let rq_topic = self.ros_context.domain_participant().create_topic(
       //rq_name,
-      service_name.to_dds_name("rq", &self.node_name, "Request"),
+      service_name
+        .to_dds_name("rq", &self.node_name, "Request")
+        .map_err(|e| bad_name(&service_name, e))?,
       service_type_name.dds_request_type(),
       &request_qos,
       TopicKind::NoKey,
     )?;
     let rs_topic = self.ros_context.domain_participant().create_topic(
-      service_name.to_dds_name("rr", &self.node_name, "Reply"),
+      service_name
+        .to_dds_name("rr", &self.node_name, "Reply")
+        .map_err(|e| bad_name(&service_name, e))?,
       service_type_name.dds_response_type(),
       &response_qos,
       TopicKind::NoKey,
@@ -1430,6 +2290,34 @@ impl Node {
     Ok(s)
   }
 
+  /// Creates a client for the Parameter Services of another Node.
+  ///
+  /// # Arguments
+  ///
+  /// * `target_node` - Node whose Parameters are accessed
+  /// * `service_mapping` - ServiceMapping used by the target Node. See
+  ///   [`Self.create_client`].
+  pub fn create_parameter_client(
+    &mut self,
+    target_node: &NodeName,
+    service_mapping: ServiceMapping,
+  ) -> CreateResult<ParameterClient> {
+    ParameterClient::new(self, target_node, service_mapping, parameter_service_qos())
+  }
+
+  /// Creates a Subscription to Parameter change notifications of other Nodes.
+  ///
+  /// If `nodes` is empty, notifications from all Nodes are received.
+  /// Otherwise, only from the listed Nodes.
+  pub fn create_parameter_event_subscription(
+    &mut self,
+    nodes: &[NodeName],
+  ) -> CreateResult<ParameterEventSubscription> {
+    let topic = self.ros_context.get_parameter_events_topic();
+    let subscription = self.create_subscription(&topic, None)?;
+    Ok(ParameterEventSubscription::new(subscription, nodes))
+  }
+
   pub fn create_action_client<A>(
     &mut self,
     service_mapping: ServiceMapping,
@@ -1442,6 +2330,9 @@ impl Node {
   {
     // action name is e.g. "/turtle1/rotate_absolute"
     // action type name is e.g. "turtlesim/action/RotateAbsolute"
+    let action_name = &self
+      .resolve_name(action_name)
+      .map_err(|e| bad_name(action_name, e))?;
     let services_base_name = action_name.push("_action");
 
     //let goal_service_name = action_name.to_owned() + "/_action/send_goal";
@@ -1517,6 +2408,9 @@ impl Node {
   where
     A: ActionTypes + 'static,
   {
+    let action_name = &self
+      .resolve_name(action_name)
+      .map_err(|e| bad_name(action_name, e))?;
     let services_base_name = action_name.push("_action");
 
     //let goal_service_name = action_name.to_owned() + "/_action/send_goal";
@@ -1583,6 +2477,113 @@ impl Node {
   }
 } // impl Node
 
+fn bad_name(name: &Name, e: NameError) -> CreateError {
+  CreateError::BadParameter {
+    reason: format!("Name {name}: {e}"),
+  }
+}
+
+// ROS 2 names and types of the (DDS Topic name, DDS type name) endpoints
+fn names_and_types(
+  endpoints: Vec<(String, String)>,
+  kind_filter: impl Fn(DdsTopicKind) -> bool,
+) -> NamesAndTypes {
+  let mut result = NamesAndTypes::new();
+  for (dds_topic, dds_type) in endpoints {
+    match graph::demangle_topic_name(&dds_topic) {
+      Some((kind, name)) if kind_filter(kind) => {
+        graph::insert_name_and_type(&mut result, name, graph::demangle_type_name(&dds_type));
+      }
+      _ => {}
+    }
+  }
+  result
+}
+
+// Endpoints of the DDS Topic, with their owning Nodes
+fn topic_endpoint_infos<'a>(
+  dds_name: &str,
+  kind: EndpointKind,
+  endpoints: impl Iterator<Item = &'a EndpointDescription>,
+  node_infos: &[NodeEntitiesInfo],
+) -> Vec<TopicEndpointInfo> {
+  let owner_of = |gid: Gid| {
+    node_infos
+      .iter()
+      .find(|info| match kind {
+        EndpointKind::Publisher => info.writers().contains(&gid),
+        EndpointKind::Subscription => info.readers().contains(&gid),
+      })
+      .map(|info| info.node_name().clone())
+  };
+  endpoints
+    .filter(|e| e.topic_name == dds_name)
+    .map(|e| TopicEndpointInfo::new(e, kind, owner_of(Gid::from(e.guid))))
+    .collect()
+}
+
+fn without_hidden(mut names_and_types: NamesAndTypes, include_hidden: bool) -> NamesAndTypes {
+  if !include_hidden {
+    names_and_types.retain(|name, _types| !graph::is_hidden_name(name));
+  }
+  names_and_types
+}
+
+// Actions are recognized by their "send_goal" Service
+fn actions_of_services(services: NamesAndTypes) -> NamesAndTypes {
+  let mut actions = NamesAndTypes::new();
+  for (service_name, service_types) in services {
+    for service_type in service_types {
+      if let Some((name, action_type)) = graph::action_name_and_type(&service_name, &service_type) {
+        graph::insert_name_and_type(&mut actions, name, action_type);
+      }
+    }
+  }
+  actions
+}
+
+// Declared parameter values, replaced by values from parameter files and then
+// from the command line. Those are read from YAML, so here we know if
+// e.g. "off" is meant as a string or a boolean.
+fn initial_parameters(
+  declared_parameters: &[Parameter],
+  parameter_descriptors: &BTreeMap<String, ParameterDescriptor>,
+  file_parameters: Vec<Parameter>,
+  parameter_overrides: Vec<Parameter>,
+  allow_undeclared_parameters: bool,
+) -> BTreeMap<String, ParameterValue> {
+  let mut parameters = declared_parameters
+    .iter()
+    .cloned()
+    .map(|Parameter { name, value }| (name, value))
+    .collect::<BTreeMap<String, ParameterValue>>();
+  let from_yaml = |Parameter { name, value }| {
+    let expected = parameter_descriptors
+      .get(&name)
+      .map(|d| d.param_type)
+      .unwrap_or(ParameterType::NotSet);
+    (name, resolve_yaml_booleans(value, expected))
+  };
+
+  // Values from parameter files and then from command line replace declared
+  // values.
+  for (name, value) in file_parameters.into_iter().map(from_yaml) {
+    if allow_undeclared_parameters || parameters.contains_key(&name) {
+      parameters.insert(name, value);
+    } else {
+      warn!("Parameter file sets undeclared parameter '{name}'. Ignoring.");
+    }
+  }
+  for (name, value) in parameter_overrides.into_iter().map(from_yaml) {
+    if allow_undeclared_parameters || parameters.contains_key(&name) {
+      parameters.insert(name, value);
+    } else {
+      warn!("Command line sets undeclared parameter '{name}'. Ignoring.");
+    }
+  }
+  parameters
+}
+
 impl Drop for Node {
   fn drop(&mut self) {
     if let Some(ref stop_spin_sender) = self.stop_spin_sender {
@@ -1597,6 +2598,39 @@ impl Drop for Node {
   }
 }
 
+/// Keeps a Reader or Writer listed in the ROS discovery info of the Node that
+/// created it. When dropped, the endpoint is removed from the Node, and the
+/// change is announced to ROS discovery.
+pub(crate) struct EndpointRegistration {
+  gid: Gid,
+  node_name: String, // fully qualified
+  // Weak, so that the Node can go away first. Then there is nothing to update.
+  node_endpoints: Weak<Mutex<BTreeSet<Gid>>>,
+  ros_context: Context,
+}
+
+impl EndpointRegistration {
+  fn new(node: &Node, gid: Gid, node_endpoints: &Arc<Mutex<BTreeSet<Gid>>>) -> Self {
+    EndpointRegistration {
+      gid,
+      node_name: node.fully_qualified_name(),
+      node_endpoints: Arc::downgrade(node_endpoints),
+      ros_context: node.ros_context.clone(),
+    }
+  }
+}
+
+impl Drop for EndpointRegistration {
+  fn drop(&mut self) {
+    if let Some(node_endpoints) = self.node_endpoints.upgrade() {
+      node_endpoints.lock().unwrap().remove(&self.gid);
+      self
+        .ros_context
+        .remove_node_endpoint(&self.node_name, self.gid);
+    }
+  }
+}
+
 /// Macro for writing to [rosout](https://wiki.ros.org/rosout) topic.
 ///
 /// # Example
@@ -1632,118 +2666,822 @@ macro_rules! rosout {
     );
 }
 
-/// Future type for waiting Readers to appear over ROS2 Topic.
-///
-/// Produced by `node.wait_for_reader(writer_guid)`
+// Local Readers (or Writers) and the remote Writers (or Readers) matched to
+// them. Updated by Spinner.
 //
-// This is implemented as a separate struct instead of just async function in
-// Node so that it does not borrow the node and thus can be Send.
-//use pin_project::pin_project;
-
-pub enum ReaderWait<'a> {
-  // We need to wait for an event that is for us
-  Wait {
-    this_writer: GUID, // Writer who is waiting for Readers to appear
-    status_event_stream: stream::BoxStream<'a,NodeEvent>,
-
-  },
-  // No need to wait, can resolve immediately.
-  Ready,
+// Tasks waiting for a match register their Wakers here, under the same lock
+// that Spinner holds when updating, so no match can go unnoticed.
+#[derive(Default)]
+pub(crate) struct MatchedEndpoints {
+  matched: BTreeMap<GUID, BTreeSet<GUID>>,
+  // Owning ROS Nodes of the matched remotes, as last reported in MatchEvents
+  owners: BTreeMap<GUID, Option<NodeName>>,
+  waiters: BTreeMap<GUID, Vec<task::Waker>>,
+  // matched_events() streams of local endpoints
+  listeners: BTreeMap<GUID, Vec<async_channel::Sender<MatchEvent>>>,
+  // Between Spinner::spin() and the end of the Spinner
+  spinner_running: bool,
 }
 
+impl MatchedEndpoints {
+  fn insert(&mut self, local: GUID, remote: GUID, node: Option<NodeName>) {
+    if self.matched.entry(local).or_default().insert(remote) {
+      // Keep an owner that is already known, so that all events agree.
+      let owner = self.owners.entry(remote).or_insert(None);
+      if owner.is_none() {
+        *owner = node;
+      }
+      let event = MatchEvent::Matched(self.endpoint(remote));
+      self.notify(local, event);
+    }
+    for waker in self.waiters.remove(&local).unwrap_or_default() {
+      waker.wake();
+    }
+  }
 
-impl Future for ReaderWait<'_> {
-  type Output = ();
-
-  fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-    match *self {
-      ReaderWait::Ready => Poll::Ready(()),
-
-      ReaderWait::Wait {
-        this_writer,
-        ref mut status_event_stream,
-      } => {
-        debug!("wait_for_reader: Waiting for a reader.");
-        loop {
-          match status_event_stream.poll_next_unpin(cx) {
-            // Check if we have RemoteReaderMatched event and it is for this_writer
-            Poll::Ready(Some(NodeEvent::DDS(DomainParticipantStatusEvent::RemoteReaderMatched {
-              local_writer, remote_reader
-            })))
-              if local_writer == this_writer => {
-                debug!("wait_for_reader: Matched remote reader {remote_reader:?}.");
-                return Poll::Ready(())
-              }
-
-            Poll::Ready(_) => {
-              // Received something else, such as other event or error
-              debug!("wait_for_reader: other event. Continue polling.");
-              // So we do nothing but go to the next iteration.
-            }
+  fn remove_remote(&mut self, remote: GUID) {
+    let mut unmatched = Vec::new();
+    for (local, remotes) in self.matched.iter_mut() {
+      if remotes.remove(&remote) {
+        unmatched.push(*local);
+      }
+    }
+    let event = MatchEvent::Unmatched(self.endpoint(remote));
+    self.owners.remove(&remote);
+    for local in unmatched {
+      self.notify(local, event.clone());
+    }
+  }
 
-            Poll::Pending => return Poll::Pending,
-          }
+  // Report Matched again for remotes whose owning Node became known.
+  fn update_owners(&mut self, owner_of: impl Fn(GUID) -> Option<NodeName>) {
+    let mut found = Vec::new();
+    for (remote, owner) in self.owners.iter_mut() {
+      if owner.is_none() {
+        *owner = owner_of(*remote);
+        if owner.is_some() {
+          found.push(*remote);
         }
       }
     }
+    for remote in found {
+      let event = MatchEvent::Matched(self.endpoint(remote));
+      let locals: Vec<GUID> = self
+        .matched
+        .iter()
+        .filter(|(_local, remotes)| remotes.contains(&remote))
+        .map(|(local, _remotes)| *local)
+        .collect();
+      for local in locals {
+        self.notify(local, event.clone());
+      }
+    }
+  }
+
+  fn endpoint(&self, remote: GUID) -> MatchedEndpoint {
+    MatchedEndpoint {
+      guid: remote,
+      node: self.owners.get(&remote).cloned().flatten(),
+    }
+  }
+
+  fn remotes(&self) -> Vec<GUID> {
+    self.matched.values().flatten().copied().collect()
+  }
+
+  fn notify(&mut self, local: GUID, event: MatchEvent) {
+    if let Some(senders) = self.listeners.get_mut(&local) {
+      senders.retain(|sender| sender.try_send(event.clone()).is_ok());
+    }
+  }
+
+  // Forget one Waker registered by a MatchWait.
+  fn remove_waiter(&mut self, local: GUID, waker: &task::Waker) {
+    if let Some(waiters) = self.waiters.get_mut(&local) {
+      if let Some(i) = waiters.iter().position(|w| w.will_wake(waker)) {
+        waiters.swap_remove(i);
+      }
+      if waiters.is_empty() {
+        self.waiters.remove(&local);
+      }
+    }
+  }
+
+  fn count(&self, local: GUID) -> Option<usize> {
+    self.matched.get(&local).map(BTreeSet::len)
+  }
+
+  fn spinner_started(&mut self) {
+    self.spinner_running = true;
+  }
+
+  // No more updates are coming, so wake up everyone to notice that.
+  fn spinner_stopped(&mut self) {
+    self.spinner_running = false;
+    // Dropping Senders ends the matched_events() streams.
+    self.listeners.clear();
+    for waker in std::mem::take(&mut self.waiters).into_values().flatten() {
+      waker.wake();
+    }
   }
 }
 
-/// Future type for waiting Writers to appear over ROS2 Topic.
+/// Change in the remote Readers or Writers matched to a [`Publisher`] or
+/// [`Subscription`].
 ///
-/// Produced by `node.wait_for_writer(writer_guid)`
+/// Produced by e.g. [`Publisher::matched_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchEvent {
+  Matched(MatchedEndpoint),
+  /// The remote endpoint, or its DomainParticipant, was lost.
+  Unmatched(MatchedEndpoint),
+}
+
+/// Remote Reader or Writer in a [`MatchEvent`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedEndpoint {
+  pub guid: GUID,
+  /// ROS Node owning the remote endpoint. `None`, if the endpoint is not
+  /// announced in ROS discovery (yet), e.g. it belongs to a plain DDS
+  /// application. If the owner becomes known later, `Matched` is sent again
+  /// with it. `Unmatched` has the owner of the last `Matched`.
+  pub node: Option<NodeName>,
+}
+
+/// Future type for waiting until a local Reader or Writer is matched with at
+/// least one remote Writer or Reader.
+///
+/// Produced by e.g. [`Publisher::wait_for_subscription`].
 //
 // This is implemented as a separate struct instead of just async function in
 // Node so that it does not borrow the node and thus can be Send.
-pub enum WriterWait<'a> {
-  // We need to wait for an event that is for us
-  Wait {
-    this_reader: GUID,
-    status_event_stream: stream::BoxStream<'a,NodeEvent>,
-  },
-  // No need to wait, can resolve immediately.
-  Ready,
+pub struct MatchWait {
+  local: GUID,
+  matched: Arc<Mutex<MatchedEndpoints>>,
+  // Set, if we know from the start that waiting is not possible.
+  error: Option<DiscoveryError>,
+  // Registered in MatchedEndpoints.waiters, to be removed when no longer
+  // needed.
+  waker: Option<task::Waker>,
 }
 
-impl Future for WriterWait<'_> {
-  type Output = ();
-
-  fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-    match *self {
-      WriterWait::Ready => Poll::Ready(()),
-
-      WriterWait::Wait {
-        this_reader,
-        ref mut status_event_stream,
-      } => {
-        debug!("wait_for_writer: Waiting for a writer.");
-        loop {
-          // We loop to pump events out of the stream until we get the desired event
-          // or "Pending". If we stop at the first event, then there is no waker
-          // installed and we are stuck.
-          match status_event_stream.poll_next_unpin(cx) {
-            // Check if we have RemoteWriterMatched event and it is for this_writer
-            Poll::Ready(Some(NodeEvent::DDS(DomainParticipantStatusEvent::RemoteWriterMatched {
-              local_reader,
-              remote_writer,
-            })))
-              if local_reader == this_reader =>
-            {
-              debug!("wait_for_writer: Matched remote writer {remote_writer:?}.");
-              return Poll::Ready(())
-            }
+impl MatchWait {
+  fn new(node: &Node, local: GUID, matched: &Arc<Mutex<MatchedEndpoints>>) -> MatchWait {
+    MatchWait {
+      local,
+      matched: Arc::clone(matched),
+      error: if node.have_spinner() {
+        None
+      } else {
+        Some(DiscoveryError::NoSpinner)
+      },
+      waker: None,
+    }
+  }
+}
 
-            Poll::Ready(_) =>
-            {
-              // Received something else, such as other event or error
-              trace!("=== other writer. Continue polling.");
-              // No return, go to next iteration.
-            }
+impl Drop for MatchWait {
+  fn drop(&mut self) {
+    if let Some(waker) = self.waker.take() {
+      self
+        .matched
+        .lock()
+        .unwrap()
+        .remove_waiter(self.local, &waker);
+    }
+  }
+}
+
+/// Former name of [`MatchWait`], when waiting for Readers.
+///
+/// Note that the output type is now a `Result`.
+#[deprecated(note = "Renamed to MatchWait")]
+pub type ReaderWait<'a> = MatchWait;
+
+/// Former name of [`MatchWait`], when waiting for Writers.
+///
+/// Note that the output type is now a `Result`.
+#[deprecated(note = "Renamed to MatchWait")]
+pub type WriterWait<'a> = MatchWait;
+
+impl Future for MatchWait {
+  type Output = Result<(), DiscoveryError>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+    let this = self.get_mut();
+    if let Some(e) = this.error {
+      return Poll::Ready(Err(e));
+    }
+    let mut matched = this.matched.lock().unwrap();
+    // Previous registration is replaced, if we still need one.
+    if let Some(waker) = this.waker.take() {
+      matched.remove_waiter(this.local, &waker);
+    }
+    if matched.count(this.local).unwrap_or(0) > 0 {
+      debug!("MatchWait: {:?} is matched.", this.local);
+      Poll::Ready(Ok(()))
+    } else if !matched.spinner_running {
+      Poll::Ready(Err(DiscoveryError::NoSpinner))
+    } else {
+      matched
+        .waiters
+        .entry(this.local)
+        .or_default()
+        .push(cx.waker().clone());
+      this.waker = Some(cx.waker().clone());
+      Poll::Pending
+    }
+  }
+}
+
+// Give up waiting for a match after `timeout`.
+pub(crate) async fn wait_timeout(
+  wait: impl Future<Output = Result<(), DiscoveryError>>,
+  timeout: std::time::Duration,
+) -> Result<(), DiscoveryError> {
+  pin_mut!(wait);
+  match future::select(wait, async_io::Timer::after(timeout)).await {
+    future::Either::Left((result, _)) => result,
+    future::Either::Right(_) => Err(DiscoveryError::Timeout),
+  }
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Node in a new Context
+  fn test_node(name: &str, options: NodeOptions) -> Node {
+    test_node_in(&Context::new().unwrap(), name, options)
+  }
+
+  fn test_node_in(context: &Context, name: &str, options: NodeOptions) -> Node {
+    context
+      .new_node(NodeName::new("/rustdds", name).unwrap(), options)
+      .unwrap()
+  }
+
+  // Discovered Reader or Writer
+  fn test_endpoint(id: u8, topic: &str, dds_type: &str) -> EndpointDescription {
+    EndpointDescription {
+      updated_time: chrono::Utc::now(),
+      guid: GUID::from_bytes([id; 16]),
+      topic_name: topic.to_owned(),
+      type_name: dds_type.to_owned(),
+      qos: QosPolicies::qos_none(),
+    }
+  }
+
+  #[test]
+  fn test_match_wait() {
+    let local = GUID::from_bytes([1; 16]);
+    let remote = GUID::from_bytes([2; 16]);
+    let matched = Arc::new(Mutex::new(MatchedEndpoints::default()));
+    let new_wait = || MatchWait {
+      local,
+      matched: Arc::clone(&matched),
+      error: None,
+      waker: None,
+    };
+    let (sender, events) = async_channel::unbounded();
+    matched
+      .lock()
+      .unwrap()
+      .listeners
+      .insert(local, vec![sender]);
+    let waker = futures::task::noop_waker();
+    let mut cx = task::Context::from_waker(&waker);
+    let remote_endpoint = MatchedEndpoint {
+      guid: remote,
+      node: None,
+    };
+
+    // Nothing to wait for until the Spinner runs.
+    assert_eq!(
+      new_wait().poll_unpin(&mut cx),
+      Poll::Ready(Err(DiscoveryError::NoSpinner))
+    );
+    matched.lock().unwrap().spinner_started();
+
+    // Match arriving after the first poll is noticed.
+    let mut wait = new_wait();
+    assert!(wait.poll_unpin(&mut cx).is_pending());
+    assert_eq!(matched.lock().unwrap().waiters[&local].len(), 1);
+    matched.lock().unwrap().insert(local, remote, None);
+    assert!(matched.lock().unwrap().waiters.is_empty());
+    assert_eq!(wait.poll_unpin(&mut cx), Poll::Ready(Ok(())));
+
+    // Lost matches are forgotten, and stopped Spinner ends waiting.
+    matched.lock().unwrap().remove_remote(remote);
+    assert_eq!(
+      events.try_recv(),
+      Ok(MatchEvent::Matched(remote_endpoint.clone()))
+    );
+    assert_eq!(
+      events.try_recv(),
+      Ok(MatchEvent::Unmatched(remote_endpoint))
+    );
+    let mut wait = new_wait();
+    assert!(wait.poll_unpin(&mut cx).is_pending());
+    matched.lock().unwrap().spinner_stopped();
+    assert_eq!(
+      wait.poll_unpin(&mut cx),
+      Poll::Ready(Err(DiscoveryError::NoSpinner))
+    );
+    assert!(events.is_closed());
+
+    // Waiting is possible again with a new Spinner, and a dropped wait does not
+    // leave its Waker behind.
+    matched.lock().unwrap().spinner_started();
+    let mut wait = new_wait();
+    assert!(wait.poll_unpin(&mut cx).is_pending());
+    assert!(wait.poll_unpin(&mut cx).is_pending());
+    assert_eq!(matched.lock().unwrap().waiters[&local].len(), 1);
+    drop(wait);
+    assert!(matched.lock().unwrap().waiters.is_empty());
+  }
+
+  #[test]
+  fn test_wait_timeout() {
+    let local = GUID::from_bytes([1; 16]);
+    let matched = Arc::new(Mutex::new(MatchedEndpoints::default()));
+    matched.lock().unwrap().spinner_started();
+    let new_wait = || MatchWait {
+      local,
+      matched: Arc::clone(&matched),
+      error: None,
+      waker: None,
+    };
+    let timeout = std::time::Duration::from_millis(50);
+
+    // No match: times out, and leaves no Waker behind.
+    let result = futures::executor::block_on(wait_timeout(new_wait(), timeout));
+    assert_eq!(result, Err(DiscoveryError::Timeout));
+    assert!(matched.lock().unwrap().waiters.is_empty());
 
-            Poll::Pending => return Poll::Pending,
+    // Match: no timeout
+    matched
+      .lock()
+      .unwrap()
+      .insert(local, GUID::from_bytes([2; 16]), None);
+    let result = futures::executor::block_on(wait_timeout(new_wait(), timeout));
+    assert_eq!(result, Ok(()));
+  }
+
+  #[test]
+  fn test_matched_owner_becomes_known() {
+    let local = GUID::from_bytes([1; 16]);
+    let remote = GUID::from_bytes([2; 16]);
+    let owner = NodeName::new("/", "talker").unwrap();
+    let mut matched = MatchedEndpoints::default();
+    let (sender, events) = async_channel::unbounded();
+    matched.listeners.insert(local, vec![sender]);
+    let endpoint = |node| MatchedEndpoint { guid: remote, node };
+
+    matched.insert(local, remote, None);
+    matched.update_owners(|_| None);
+    matched.update_owners(|_| Some(owner.clone()));
+    matched.update_owners(|_| Some(owner.clone()));
+    matched.remove_remote(remote);
+    assert_eq!(events.try_recv(), Ok(MatchEvent::Matched(endpoint(None))));
+    assert_eq!(
+      events.try_recv(),
+      Ok(MatchEvent::Matched(endpoint(Some(owner.clone()))))
+    );
+    assert_eq!(
+      events.try_recv(),
+      Ok(MatchEvent::Unmatched(endpoint(Some(owner))))
+    );
+    assert!(events.try_recv().is_err());
+    assert!(matched.owners.is_empty());
+  }
+
+  #[test]
+  fn test_set_parameters_atomically_rolls_back() {
+    // The set action records applied values, and refuses negative values.
+    let applied = Arc::new(Mutex::new(Vec::new()));
+    let action_applied = Arc::clone(&applied);
+    let mut node = test_node(
+      "test_atomic_node",
+      NodeOptions::new()
+        .declare_parameter("a", ParameterValue::Integer(1))
+        .declare_parameter("b", ParameterValue::Integer(2))
+        .parameter_set_action(Box::new(move |name, value| {
+          action_applied
+            .lock()
+            .unwrap()
+            .push((name.to_owned(), value.clone()));
+          match value {
+            ParameterValue::Integer(i) if *i < 0 => Err("Must not be negative.".to_owned()),
+            _ => Ok(()),
           }
-        }
+        })),
+    );
+    let spinner = node.spinner().unwrap();
+    applied.lock().unwrap().clear(); // forget the declarations
+
+    let result = spinner.set_parameters_atomically(vec![
+      Parameter {
+        name: "a".to_owned(),
+        value: ParameterValue::Integer(10),
+      },
+      Parameter {
+        name: "b".to_owned(),
+        value: ParameterValue::Integer(-20),
+      },
+    ]);
+    assert!(result.is_err());
+    assert!(matches!(
+      node.get_parameter("a"),
+      Some(ParameterValue::Integer(1))
+    ));
+    assert!(matches!(
+      node.get_parameter("b"),
+      Some(ParameterValue::Integer(2))
+    ));
+    // The action on "a" was undone with its previous value.
+    let applied: Vec<(String, i64)> = applied
+      .lock()
+      .unwrap()
+      .iter()
+      .filter_map(|(name, value)| match value {
+        ParameterValue::Integer(i) => Some((name.clone(), *i)),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      applied,
+      vec![
+        ("a".to_owned(), 10),
+        ("b".to_owned(), -20),
+        ("a".to_owned(), 1)
+      ]
+    );
+  }
+
+  #[test]
+  fn test_set_parameters_atomically_excludes_concurrent_set() {
+    // The set action of "a" stalls, so that another set can try to get in
+    // between.
+    let (started_sender, started_receiver) = std::sync::mpsc::channel();
+    let started_sender = Mutex::new(started_sender);
+    let mut node = test_node(
+      "test_atomic_race_node",
+      NodeOptions::new()
+        .declare_parameter("a", ParameterValue::Integer(1))
+        .declare_parameter("b", ParameterValue::Integer(2))
+        .parameter_set_action(Box::new(move |name, value| {
+          if name == "a" && matches!(value, ParameterValue::Integer(10)) {
+            started_sender.lock().unwrap().send(()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(200));
+          }
+          Ok(())
+        })),
+    );
+    let spinner = node.spinner().unwrap();
+
+    std::thread::scope(|scope| {
+      scope.spawn(|| {
+        spinner
+          .set_parameters_atomically(vec![
+            Parameter {
+              name: "a".to_owned(),
+              value: ParameterValue::Integer(10),
+            },
+            Parameter {
+              name: "b".to_owned(),
+              value: ParameterValue::Integer(20),
+            },
+          ])
+          .unwrap()
+      });
+      started_receiver.recv().unwrap();
+      // This must wait for the atomic set, not be overwritten by it.
+      node.set_parameter("b", ParameterValue::Integer(5)).unwrap();
+    });
+    assert!(matches!(
+      node.get_parameter("a"),
+      Some(ParameterValue::Integer(10))
+    ));
+    assert!(matches!(
+      node.get_parameter("b"),
+      Some(ParameterValue::Integer(5))
+    ));
+  }
+
+  #[test]
+  fn test_parameter_change_stream() {
+    let node = test_node(
+      "test_change_node",
+      NodeOptions::new()
+        .declare_parameter("speed", ParameterValue::Integer(1))
+        .parameter_validator(Box::new(|_name, value| match value {
+          ParameterValue::Integer(i) if *i < 0 => Err("Must not be negative.".to_owned()),
+          _ => Ok(()),
+        })),
+    );
+    let changes = node.parameter_change_stream();
+    let dropped = node.parameter_change_stream();
+    drop(dropped);
+
+    node
+      .set_parameter("speed", ParameterValue::Integer(2))
+      .unwrap();
+    assert!(node
+      .set_parameter("speed", ParameterValue::Integer(-1))
+      .is_err());
+    node.undeclare_parameter("speed");
+
+    let change = changes.try_recv().unwrap();
+    assert_eq!(change.name, "speed");
+    assert!(matches!(change.old, Some(ParameterValue::Integer(1))));
+    assert!(matches!(change.new, ParameterValue::Integer(2)));
+    // Rejected value is not reported
+    let change = changes.try_recv().unwrap();
+    assert!(matches!(change.old, Some(ParameterValue::Integer(2))));
+    assert!(matches!(change.new, ParameterValue::NotSet));
+    assert!(changes.try_recv().is_err());
+    assert_eq!(node.parameter_change_senders.lock().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_participant_lost() {
+    let mut node = test_node("test_lost_node", NodeOptions::new());
+    let spinner = node.spinner().unwrap();
+    let status = node.status_receiver().unwrap();
+
+    // Participant 7 goes away, participant 8 stays.
+    let lost = GUID::from_bytes([7; 16]);
+    let kept = GUID::from_bytes([8; 16]);
+    for participant in [lost, kept] {
+      let nodes = vec![NodeEntitiesInfo::new(NodeName::new("/", "remote").unwrap())];
+      node
+        .external_nodes
+        .lock()
+        .unwrap()
+        .insert(Gid::from(participant), nodes);
+    }
+    let mut lost_writer = test_endpoint(7, "rt/chatter", "std_msgs::msg::dds_::String_");
+    lost_writer.guid = GUID::from_bytes([7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 0, 0, 1, 2]);
+    let lost_writer_guid = lost_writer.guid;
+    node
+      .discovered_writers
+      .lock()
+      .unwrap()
+      .insert(lost_writer_guid, lost_writer);
+    let kept_reader = test_endpoint(8, "rt/chatter", "std_msgs::msg::dds_::String_");
+    node
+      .discovered_readers
+      .lock()
+      .unwrap()
+      .insert(kept_reader.guid, kept_reader);
+
+    spinner.participant_lost(lost);
+
+    let external_nodes = node.external_nodes.lock().unwrap();
+    assert_eq!(
+      external_nodes.keys().copied().collect::<Vec<_>>(),
+      vec![Gid::from(kept)]
+    );
+    assert!(node.discovered_writers.lock().unwrap().is_empty());
+    assert_eq!(node.discovered_readers.lock().unwrap().len(), 1);
+
+    match status.try_recv() {
+      Ok(NodeEvent::ROS(info)) => {
+        assert_eq!(info.gid(), Gid::from(lost));
+        assert!(info.nodes().is_empty());
       }
+      other => panic!("Unexpected status event {:?}", other),
     }
+    assert!(status.try_recv().is_err());
   }
-}
+
+  #[test]
+  fn test_matched_events_needs_running_spinner() {
+    let mut node = test_node("test_matched_events_node", NodeOptions::new());
+    let local = GUID::from_bytes([1; 16]);
+    assert_eq!(
+      node.subscription_matched_events(local).err(),
+      Some(DiscoveryError::NoSpinner)
+    );
+    // Created, but not spinning
+    let spinner = node.spinner().unwrap();
+    assert_eq!(
+      node.subscription_matched_events(local).err(),
+      Some(DiscoveryError::NoSpinner)
+    );
+    let spin = spinner.spin();
+    assert!(node.subscription_matched_events(local).is_ok());
+    drop(spin);
+    assert_eq!(
+      node.subscription_matched_events(local).err(),
+      Some(DiscoveryError::NoSpinner)
+    );
+    assert_eq!(
+      node.publisher_matched_events(local).err(),
+      Some(DiscoveryError::NoSpinner)
+    );
+  }
+
+  #[test]
+  fn test_passive_observer_hides_itself() {
+    let context =
+      Context::with_options(crate::ContextOptions::new().passive_observer(true)).unwrap();
+    let mut node = test_node_in(
+      &context,
+      "test_observer_node",
+      NodeOptions::new().enable_rosout(false),
+    );
+    let spinner = node.spinner().unwrap();
+    let string = "std_msgs::msg::dds_::String_";
+
+    let own_prefix = context.domain_participant().guid().prefix;
+    let mut own_writer = test_endpoint(1, "rt/own_topic", string);
+    own_writer.guid.prefix = own_prefix;
+    assert!(!spinner.endpoint_detected(&node.discovered_writers, &own_writer));
+    let remote_writer = test_endpoint(2, "rt/chatter", string);
+    assert!(spinner.endpoint_detected(&node.discovered_writers, &remote_writer));
+
+    let mut talker = NodeEntitiesInfo::new(NodeName::new("/", "talker").unwrap());
+    talker.add_writer(Gid::from(remote_writer.guid));
+    node
+      .external_nodes
+      .lock()
+      .unwrap()
+      .insert(Gid::from(GUID::from_bytes([9; 16])), vec![talker]);
+
+    assert_eq!(
+      node.get_node_names(),
+      vec![NodeName::new("/", "talker").unwrap()]
+    );
+    assert_eq!(
+      node
+        .get_topic_names_and_types(false)
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>(),
+      vec!["/chatter"]
+    );
+    let snapshot = node.graph_snapshot();
+    assert_eq!(snapshot.nodes, vec!["/talker"]);
+    assert_eq!(snapshot.topics.len(), 1);
+    assert_eq!(snapshot.topics[0].name, "/chatter");
+  }
+
+  #[test]
+  fn test_initial_parameters_keep_string_type() {
+    let declared = vec![
+      Parameter {
+        name: "mode".to_string(),
+        value: ParameterValue::String("auto".to_string()),
+      },
+      Parameter {
+        name: "verbose".to_string(),
+        value: ParameterValue::Boolean(true),
+      },
+    ];
+    let descriptors = declared
+      .iter()
+      .map(|p| {
+        (
+          p.name.clone(),
+          ParameterDescriptor::from_value(&p.name, &p.value),
+        )
+      })
+      .collect::<BTreeMap<_, _>>();
+    let from_yaml = |name: &str, yaml: &str| Parameter {
+      name: name.to_string(),
+      value: parse_parameter_value(yaml).unwrap(),
+    };
+    let file_parameters = vec![from_yaml("mode", "\"off\""), from_yaml("other", "on")];
+    let overrides = vec![from_yaml("verbose", "off")];
+
+    let parameters = initial_parameters(
+      &declared,
+      &descriptors,
+      file_parameters.clone(),
+      overrides.clone(),
+      true,
+    );
+    assert!(matches!(&parameters["mode"], ParameterValue::String(s) if s == "off"));
+    assert!(matches!(
+      parameters["verbose"],
+      ParameterValue::Boolean(false)
+    ));
+    assert!(matches!(parameters["other"], ParameterValue::Boolean(true)));
+    for name in ["mode", "verbose"] {
+      assert!(descriptors[name].validate(None, &parameters[name]).is_ok());
+    }
+
+    // Without allow_undeclared_parameters, files cannot add parameters either.
+    let parameters = initial_parameters(&declared, &descriptors, file_parameters, overrides, false);
+    assert!(matches!(&parameters["mode"], ParameterValue::String(s) if s == "off"));
+    assert!(!parameters.contains_key("other"));
+  }
+
+  #[test]
+  fn test_names_and_types() {
+    let endpoints = [
+      (
+        "rq/add_two_intsRequest",
+        "example_interfaces::srv::dds_::AddTwoInts_Request_",
+      ),
+      (
+        "rq/fib/_action/send_goalRequest",
+        "ex::action::dds_::Fibonacci_SendGoal_Request_",
+      ),
+      (
+        "rq/fib/_action/get_resultRequest",
+        "ex::action::dds_::Fibonacci_GetResult_Request_",
+      ),
+      ("rt/chatter", "std_msgs::msg::dds_::String_"),
+      (
+        "rt/fib/_action/feedback",
+        "ex::action::dds_::Fibonacci_FeedbackMessage_",
+      ),
+    ]
+    .iter()
+    .map(|(topic, dds_type)| (topic.to_string(), dds_type.to_string()))
+    .collect::<Vec<_>>();
+    let services = names_and_types(endpoints.clone(), |kind| kind != DdsTopicKind::Topic);
+    let topics = names_and_types(endpoints, |kind| kind == DdsTopicKind::Topic);
+    let names = |names_and_types: NamesAndTypes| names_and_types.into_keys().collect::<Vec<_>>();
+
+    assert_eq!(
+      names(without_hidden(services.clone(), false)),
+      vec!["/add_two_ints"]
+    );
+    assert_eq!(
+      names(without_hidden(services.clone(), true)),
+      vec![
+        "/add_two_ints",
+        "/fib/_action/get_result",
+        "/fib/_action/send_goal"
+      ]
+    );
+    assert_eq!(
+      services["/add_two_ints"],
+      vec!["example_interfaces/srv/AddTwoInts"]
+    );
+    assert_eq!(names(actions_of_services(services)), vec!["/fib"]);
+    assert_eq!(
+      names(without_hidden(topics.clone(), false)),
+      vec!["/chatter"]
+    );
+    assert_eq!(
+      names(without_hidden(topics, true)),
+      vec!["/chatter", "/fib/_action/feedback"]
+    );
+  }
+
+  #[test]
+  fn test_topic_endpoint_infos() {
+    let string = "std_msgs::msg::dds_::String_";
+    let mut talker = NodeEntitiesInfo::new(NodeName::new("/", "talker").unwrap());
+    talker.add_writer(Gid::from(GUID::from_bytes([1; 16])));
+    let writers = [
+      test_endpoint(1, "rt/rustdds/chatter", string),
+      test_endpoint(2, "rt/rustdds/chatter", string),
+      test_endpoint(3, "rt/other", string),
+    ];
+    // Relative name is resolved in the Node namespace.
+    let dds_name = Name::parse("chatter")
+      .unwrap()
+      .to_dds_name("rt", &NodeName::new("/rustdds", "listener").unwrap(), "")
+      .unwrap();
+
+    let publishers = topic_endpoint_infos(
+      &dds_name,
+      EndpointKind::Publisher,
+      writers.iter(),
+      &[talker.clone()],
+    );
+    assert_eq!(publishers.len(), 2);
+    assert_eq!(
+      publishers[0].node_name,
+      Some(NodeName::new("/", "talker").unwrap())
+    );
+    assert_eq!(publishers[0].endpoint_kind, EndpointKind::Publisher);
+    assert_eq!(publishers[0].topic_type, "std_msgs/msg/String");
+    assert_eq!(publishers[1].node_name, None); // not a ROS Node
+
+    // Owner is looked up among the endpoints of the same kind.
+    let subscriptions = topic_endpoint_infos(
+      &dds_name,
+      EndpointKind::Subscription,
+      writers.iter(),
+      &[talker],
+    );
+    assert_eq!(
+      subscriptions[0].endpoint_gid,
+      Gid::from(GUID::from_bytes([1; 16]))
+    );
+    assert_eq!(subscriptions[0].node_name, None);
+    assert!(
+      topic_endpoint_infos("rt/chatter", EndpointKind::Publisher, writers.iter(), &[]).is_empty()
+    );
+  }
+} // mod tests
@@ -0,0 +1,284 @@
+//! Client for the Parameter Services of a (remote) [`Node`].
+//!
+//! This is the same interface that e.g. `ros2 param` uses. See
+//! [`Node::create_parameter_client`].
+use futures::join;
+use rustdds::{
+  dds::{CreateError, CreateResult},
+  QosPolicies,
+};
+
+use crate::{
+  names::{Name, NodeName, ServiceTypeName},
+  node::{DiscoveryError, Node},
+  parameters::{
+    raw, Parameter, ParameterDescriptor, ParameterType, ParameterValue, SetParametersResult,
+  },
+  rcl_interfaces::*,
+  service::{client::CallServiceError, Client, Service, ServiceMapping},
+};
+
+/// Typed access to the Parameters of another Node.
+///
+/// The target Node must have Parameter Services enabled, and be running them,
+/// e.g. a `Spinner` for a `ros2-client` Node.
+pub struct ParameterClient {
+  target_node: NodeName,
+  get_parameters_client: Client<GetParametersService>,
+  get_parameter_types_client: Client<GetParameterTypesService>,
+  list_parameters_client: Client<ListParametersService>,
+  set_parameters_client: Client<SetParametersService>,
+  set_parameters_atomically_client: Client<SetParametersAtomicallyService>,
+  describe_parameters_client: Client<DescribeParametersService>,
+}
+
+impl ParameterClient {
+  pub(crate) fn new(
+    node: &mut Node,
+    target_node: &NodeName,
+    service_mapping: ServiceMapping,
+    qos: QosPolicies,
+  ) -> CreateResult<ParameterClient> {
+    let target = target_node.fully_qualified_name();
+    Ok(ParameterClient {
+      target_node: target_node.clone(),
+      get_parameters_client: create_client(
+        node,
+        &target,
+        "get_parameters",
+        "GetParameters",
+        service_mapping,
+        &qos,
+      )?,
+      get_parameter_types_client: create_client(
+        node,
+        &target,
+        "get_parameter_types",
+        "GetParameterTypes",
+        service_mapping,
+        &qos,
+      )?,
+      list_parameters_client: create_client(
+        node,
+        &target,
+        "list_parameters",
+        "ListParameters",
+        service_mapping,
+        &qos,
+      )?,
+      set_parameters_client: create_client(
+        node,
+        &target,
+        "set_parameters",
+        "SetParameters",
+        service_mapping,
+        &qos,
+      )?,
+      set_parameters_atomically_client: create_client(
+        node,
+        &target,
+        "set_parameters_atomically",
+        "SetParametersAtomically",
+        service_mapping,
+        &qos,
+      )?,
+      describe_parameters_client: create_client(
+        node,
+        &target,
+        "describe_parameters",
+        "DescribeParameters",
+        service_mapping,
+        &qos,
+      )?,
+    })
+  }
+
+  /// Name of the Node whose Parameters we are accessing.
+  pub fn target_node(&self) -> &NodeName {
+    &self.target_node
+  }
+
+  /// Wait until all Parameter Services of the target Node are available.
+  ///
+  /// Fails, if the Node does not have a background Spinner running.
+  pub async fn wait_for_service(&self, my_node: &Node) -> Result<(), DiscoveryError> {
+    let results = join!(
+      self.get_parameters_client.wait_for_service(my_node),
+      self.get_parameter_types_client.wait_for_service(my_node),
+      self.list_parameters_client.wait_for_service(my_node),
+      self.set_parameters_client.wait_for_service(my_node),
+      self
+        .set_parameters_atomically_client
+        .wait_for_service(my_node),
+      self.describe_parameters_client.wait_for_service(my_node),
+    );
+    results.0?;
+    results.1?;
+    results.2?;
+    results.3?;
+    results.4?;
+    results.5
+  }
+
+  /// Get Parameter values. The result has one value for each requested name.
+  /// Parameters that do not exist are [`ParameterValue::NotSet`].
+  pub async fn get(&self, names: &[&str]) -> CallResult<Vec<ParameterValue>> {
+    let response = self
+      .get_parameters_client
+      .async_call_service(GetParametersRequest {
+        names: to_strings(names),
+      })
+      .await?;
+    Ok(
+      response
+        .values
+        .into_iter()
+        .map(ParameterValue::from)
+        .collect(),
+    )
+  }
+
+  /// Get Parameter types. The result has one type for each requested name.
+  pub async fn get_types(&self, names: &[&str]) -> CallResult<Vec<ParameterType>> {
+    let response = self
+      .get_parameter_types_client
+      .async_call_service(GetParameterTypesRequest {
+        names: to_strings(names),
+      })
+      .await?;
+    Ok(
+      response
+        .values
+        .into_iter()
+        .map(ParameterType::from)
+        .collect(),
+    )
+  }
+
+  /// List Parameter names, optionally only those starting with one of the
+  /// `prefixes`.
+  ///
+  /// `depth` limits how many dot-separated levels are listed.
+  /// [`ListParametersRequest::DEPTH_RECURSIVE`] lists all.
+  pub async fn list(&self, prefixes: &[&str], depth: u64) -> CallResult<ListParametersResult> {
+    let response = self
+      .list_parameters_client
+      .async_call_service(ListParametersRequest {
+        prefixes: to_strings(prefixes),
+        depth,
+      })
+      .await?;
+    Ok(response.result)
+  }
+
+  /// Set Parameters one by one. The result tells separately for each
+  /// Parameter, if it was set.
+  pub async fn set(&self, parameters: Vec<Parameter>) -> CallResult<Vec<SetParametersResult>> {
+    let response = self
+      .set_parameters_client
+      .async_call_service(SetParametersRequest {
+        parameter: parameters.into_iter().map(raw::Parameter::from).collect(),
+      })
+      .await?;
+    Ok(
+      response
+        .results
+        .into_iter()
+        .map(SetParametersResult::from)
+        .collect(),
+    )
+  }
+
+  /// Set Parameters so that either all or none of them are set.
+  pub async fn set_atomically(
+    &self,
+    parameters: Vec<Parameter>,
+  ) -> CallResult<SetParametersResult> {
+    let response = self
+      .set_parameters_atomically_client
+      .async_call_service(SetParametersRequest {
+        parameter: parameters.into_iter().map(raw::Parameter::from).collect(),
+      })
+      .await?;
+    Ok(response.result.into())
+  }
+
+  /// Get Parameter descriptions, including constraints. The result has one
+  /// descriptor for each requested name.
+  pub async fn describe(&self, names: &[&str]) -> CallResult<Vec<ParameterDescriptor>> {
+    let response = self
+      .describe_parameters_client
+      .async_call_service(DescribeParametersRequest {
+        names: to_strings(names),
+      })
+      .await?;
+    Ok(
+      response
+        .values
+        .into_iter()
+        .map(ParameterDescriptor::from)
+        .collect(),
+    )
+  }
+}
+
+/// Result of a Parameter Service call
+pub type CallResult<T> = Result<T, CallServiceError<()>>;
+
+// Create a client for service "<target node>/<service_name>".
+fn create_client<S>(
+  node: &mut Node,
+  target: &str,
+  service_name: &str,
+  type_name: &str,
+  service_mapping: ServiceMapping,
+  qos: &QosPolicies,
+) -> CreateResult<Client<S>>
+where
+  S: Service + 'static,
+  S::Request: Clone,
+{
+  // Not all valid Node names are valid as namespaces, e.g. if they contain
+  // "__" or a namespace token starts with a digit.
+  let name = Name::new(target, service_name).map_err(|e| CreateError::BadParameter {
+    reason: format!("Parameter service name for Node {target}: {e}"),
+  })?;
+  node.create_client(
+    service_mapping,
+    &name,
+    &ServiceTypeName::new("rcl_interfaces", type_name),
+    qos.clone(),
+    qos.clone(),
+  )
+}
+
+fn to_strings(names: &[&str]) -> Vec<String> {
+  names.iter().map(|n| n.to_string()).collect()
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_parameter_client_for_unusual_node_names() {
+  let context = crate::Context::new().unwrap();
+  let mut node = context
+    .new_node(
+      NodeName::new("/rustdds", "test_parameter_client").unwrap(),
+      crate::NodeOptions::new(),
+    )
+    .unwrap();
+  let good = NodeName::new("/some_ns", "target").unwrap();
+  let client = node
+    .create_parameter_client(&good, ServiceMapping::Enhanced)
+    .unwrap();
+  assert_eq!(client.target_node(), &good);
+
+  // Valid Node names, which cannot be used as a namespace for services
+  for (namespace, base_name) in [("/", "my__node"), ("/1ns", "node")] {
+    let name = NodeName::new(namespace, base_name).unwrap();
+    assert!(node
+      .create_parameter_client(&name, ServiceMapping::Enhanced)
+      .is_err());
+  }
+}
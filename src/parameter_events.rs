@@ -0,0 +1,136 @@
+//! Reading Parameter change notifications from the `/parameter_events` topic.
+//!
+//! Every Node publishes a notification there, when its Parameters are
+//! declared, changed, or undeclared. See
+//! [`Node::create_parameter_event_subscription`](crate::Node::create_parameter_event_subscription).
+use futures::{stream::FusedStream, StreamExt};
+use rustdds::{dds::ReadResult, Timestamp};
+
+use crate::{
+  message_info::MessageInfo,
+  names::NodeName,
+  parameters::{raw, Parameter},
+  pubsub::Subscription,
+};
+
+/// Parameter changes of a single Node, in Rust-like form.
+#[derive(Debug, Clone)]
+pub struct ParameterEvent {
+  pub timestamp: Timestamp,
+  /// Fully qualified name of the Node that owns the Parameters
+  pub node: String,
+  pub new_parameters: Vec<Parameter>,
+  pub changed_parameters: Vec<Parameter>,
+  pub deleted_parameters: Vec<Parameter>,
+}
+
+impl From<raw::ParameterEvent> for ParameterEvent {
+  fn from(e: raw::ParameterEvent) -> ParameterEvent {
+    let convert = |ps: Vec<raw::Parameter>| ps.into_iter().map(Parameter::from).collect();
+    ParameterEvent {
+      timestamp: e.timestamp,
+      node: e.node,
+      new_parameters: convert(e.new_parameters),
+      changed_parameters: convert(e.changed_parameters),
+      deleted_parameters: convert(e.deleted_parameters),
+    }
+  }
+}
+
+/// Subscription to `/parameter_events`, possibly limited to some Nodes.
+pub struct ParameterEventSubscription {
+  subscription: Subscription<raw::ParameterEvent>,
+  // Fully qualified names. Empty means all Nodes.
+  node_filter: Vec<String>,
+}
+
+impl ParameterEventSubscription {
+  pub(crate) fn new(
+    subscription: Subscription<raw::ParameterEvent>,
+    nodes: &[NodeName],
+  ) -> ParameterEventSubscription {
+    ParameterEventSubscription {
+      subscription,
+      node_filter: nodes.iter().map(NodeName::fully_qualified_name).collect(),
+    }
+  }
+
+  fn accepts(&self, event: &raw::ParameterEvent) -> bool {
+    self.node_filter.is_empty() || self.node_filter.contains(&event.node)
+  }
+
+  /// Take the next event, if any is available. Events from Nodes not in the
+  /// filter are skipped.
+  pub fn take(&self) -> ReadResult<Option<(ParameterEvent, MessageInfo)>> {
+    while let Some((event, info)) = self.subscription.take()? {
+      if self.accepts(&event) {
+        return Ok(Some((event.into(), info)));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Async Stream of events. Events from Nodes not in the filter are skipped.
+  pub fn async_stream(
+    &self,
+  ) -> impl FusedStream<Item = ReadResult<(ParameterEvent, MessageInfo)>> + '_ {
+    self.subscription.async_stream().filter_map(move |result| {
+      let item = match result {
+        Ok((event, info)) if self.accepts(&event) => Some(Ok((event.into(), info))),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+      };
+      futures::future::ready(item)
+    })
+  }
+
+  /// Access the underlying Subscription, e.g. for counting publishers.
+  pub fn subscription(&self) -> &Subscription<raw::ParameterEvent> {
+    &self.subscription
+  }
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_parameter_event_filter() {
+  use crate::{Context, NodeOptions, ParameterValue};
+
+  let context = Context::new().unwrap();
+  let mut node = context
+    .new_node(
+      NodeName::new("/rustdds", "test_event_node").unwrap(),
+      NodeOptions::new(),
+    )
+    .unwrap();
+  let watched = NodeName::new("/ns", "watched").unwrap();
+  let event_from = |node: &str| raw::ParameterEvent {
+    timestamp: Timestamp::ZERO,
+    node: node.to_string(),
+    new_parameters: vec![],
+    changed_parameters: vec![Parameter {
+      name: "speed".to_string(),
+      value: ParameterValue::Double(1.5),
+    }
+    .into()],
+    deleted_parameters: vec![],
+  };
+
+  let all = node.create_parameter_event_subscription(&[]).unwrap();
+  let filtered = node
+    .create_parameter_event_subscription(&[watched])
+    .unwrap();
+  assert!(all.accepts(&event_from("/other")));
+  assert!(filtered.accepts(&event_from("/ns/watched")));
+  assert!(!filtered.accepts(&event_from("/other")));
+  assert!(!filtered.accepts(&event_from("/ns/watched/sub")));
+
+  let event = ParameterEvent::from(event_from("/ns/watched"));
+  assert_eq!(event.node, "/ns/watched");
+  assert_eq!(event.changed_parameters[0].name, "speed");
+  assert!(matches!(
+    event.changed_parameters[0].value,
+    ParameterValue::Double(d) if d == 1.5
+  ));
+}
@@ -0,0 +1,314 @@
+//! Reading [`Parameter`]s from ROS 2 parameter files.
+//!
+//! Parameter files are YAML files, where Node names are keys above a
+//! `ros__parameters` mapping. Node names may use wildcards `*` (one namespace
+//! token) and `**` (any number of tokens). Nested mappings below
+//! `ros__parameters` produce dot-separated parameter names.
+//!
+//! ```yaml
+//! /**:
+//!   ros__parameters:
+//!     use_sim_time: false
+//! /my_ns/my_node:
+//!   ros__parameters:
+//!     speed: 1.5
+//!     limits:
+//!       max: 10          # parameter "limits.max"
+//!     waypoints: [1, 2, 3]
+//!     names:
+//!       - alpha
+//!       - beta
+//! ```
+//!
+//! See [ROS 2 Parameters](https://docs.ros.org/en/rolling/Concepts/Basic/About-Parameters.html)
+//!
+//! Parameter values are scalars or sequences of scalars. Sequences must be
+//! homogeneous, except that integers and doubles may be mixed, which gives a
+//! double array. Parameters without a value are skipped.
+use std::{fmt, fs, io, path::Path};
+
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+use serde_yaml::Value;
+
+use crate::{
+  names::NodeName,
+  parameters::{parameter_value_from_yaml, Parameter},
+};
+
+const PARAMETERS_KEY: &str = "ros__parameters";
+
+/// Contents of a parameter file.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterFile {
+  // (Node name pattern, parameters) in file order
+  sections: Vec<(String, Vec<Parameter>)>,
+}
+
+/// What went wrong in reading a parameter file
+#[derive(Debug)]
+pub enum ParameterFileError {
+  Io(io::Error),
+  /// Not valid YAML. Line number starts from 1, if known.
+  Syntax {
+    line: Option<usize>,
+    reason: String,
+  },
+  /// Valid YAML, but not a parameter file, e.g. a value is not a scalar or
+  /// a sequence of scalars.
+  Invalid(String),
+}
+
+impl From<io::Error> for ParameterFileError {
+  fn from(e: io::Error) -> ParameterFileError {
+    ParameterFileError::Io(e)
+  }
+}
+
+impl From<serde_yaml::Error> for ParameterFileError {
+  fn from(e: serde_yaml::Error) -> ParameterFileError {
+    ParameterFileError::Syntax {
+      line: e.location().map(|l| l.line()),
+      reason: e.to_string(),
+    }
+  }
+}
+
+impl fmt::Display for ParameterFileError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Io(e) => write!(f, "ParameterFileError::Io : {e}"),
+      Self::Syntax { reason, .. } => write!(f, "ParameterFileError::Syntax : {reason}"),
+      Self::Invalid(reason) => write!(f, "ParameterFileError::Invalid : {reason}"),
+    }
+  }
+}
+
+impl std::error::Error for ParameterFileError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Io(e) => Some(e),
+      Self::Syntax { .. } | Self::Invalid(_) => None,
+    }
+  }
+}
+
+impl ParameterFile {
+  /// Read and parse a parameter file.
+  pub fn load(path: impl AsRef<Path>) -> Result<ParameterFile, ParameterFileError> {
+    Self::parse(&fs::read_to_string(path)?)
+  }
+
+  /// Parse parameter file contents.
+  pub fn parse(yaml: &str) -> Result<ParameterFile, ParameterFileError> {
+    let root: Value = serde_yaml::from_str(yaml)?;
+    let mut sections = Vec::new();
+    match root {
+      Value::Null => {} // empty file
+      Value::Mapping(_) => collect_sections(&root, &mut Vec::new(), &mut sections)?,
+      _ => {
+        return Err(ParameterFileError::Invalid(
+          "Expected Node names".to_owned(),
+        ))
+      }
+    }
+    Ok(ParameterFile { sections })
+  }
+
+  /// Parameters in this file that apply to the given Node, in file order.
+  ///
+  /// If the same parameter is given several times, the last one should take
+  /// effect.
+  pub fn parameters_for(&self, node_name: &NodeName) -> Vec<Parameter> {
+    let fqn = node_name.fully_qualified_name();
+    let node_tokens: Vec<&str> = fqn.split('/').filter(|t| !t.is_empty()).collect();
+    self
+      .sections
+      .iter()
+      .filter(|(pattern, _)| {
+        let pattern_tokens: Vec<&str> = pattern.split('/').filter(|t| !t.is_empty()).collect();
+        node_name_matches(&pattern_tokens, &node_tokens)
+      })
+      .flat_map(|(_, params)| params.iter().cloned())
+      .collect()
+  }
+}
+
+// Match Node name tokens against a pattern, where "*" matches exactly one
+// token and "**" any number of tokens.
+fn node_name_matches(pattern: &[&str], name: &[&str]) -> bool {
+  match pattern.split_first() {
+    None => name.is_empty(),
+    Some((&"**", rest)) => (0..=name.len()).any(|skip| node_name_matches(rest, &name[skip..])),
+    Some((p, rest)) => match name.split_first() {
+      Some((n, name_rest)) if *p == "*" || p == n => node_name_matches(rest, name_rest),
+      _ => false,
+    },
+  }
+}
+
+// Walk the YAML tree. Keys above "ros__parameters" form the Node name pattern.
+fn collect_sections(
+  node: &Value,
+  path: &mut Vec<String>,
+  sections: &mut Vec<(String, Vec<Parameter>)>,
+) -> Result<(), ParameterFileError> {
+  let entries = match node {
+    Value::Mapping(entries) => entries,
+    _ => return Ok(()), // Not a mapping. Nothing for us here.
+  };
+  for (key, value) in entries {
+    let key = key_string(key)?;
+    if key == PARAMETERS_KEY {
+      let mut pattern = path.join("/");
+      if !pattern.starts_with('/') {
+        pattern.insert(0, '/');
+      }
+      let mut parameters = Vec::new();
+      collect_parameters(value, "", &mut parameters)
+        .map_err(|reason| ParameterFileError::Invalid(format!("{pattern}: {reason}")))?;
+      sections.push((pattern, parameters));
+    } else if path.is_empty() && !value.is_mapping() {
+      return Err(ParameterFileError::Invalid(format!(
+        "{key}: Expected a Node name"
+      )));
+    } else {
+      path.push(key);
+      collect_sections(value, path, sections)?;
+      path.pop();
+    }
+  }
+  Ok(())
+}
+
+fn collect_parameters(
+  node: &Value,
+  prefix: &str,
+  parameters: &mut Vec<Parameter>,
+) -> Result<(), String> {
+  if let Value::Mapping(entries) = node {
+    for (key, value) in entries {
+      let key = key_string(key).map_err(|e| e.to_string())?;
+      let name = if prefix.is_empty() {
+        key
+      } else {
+        format!("{prefix}.{key}")
+      };
+      match value {
+        Value::Mapping(_) => collect_parameters(value, &name, parameters)?,
+        Value::Null => warn!("Parameter file: Parameter {name} has no value. Skipping."),
+        _ => {
+          let value = parameter_value_from_yaml(value)
+            .map_err(|reason| format!("Parameter {name}: {reason}"))?;
+          parameters.push(Parameter { name, value });
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+// Mapping keys are usually strings, but e.g. "1:" gives a number.
+fn key_string(key: &Value) -> Result<String, ParameterFileError> {
+  match key {
+    Value::String(s) => Ok(s.clone()),
+    Value::Number(n) => Ok(n.to_string()),
+    Value::Bool(b) => Ok(b.to_string()),
+    _ => Err(ParameterFileError::Invalid(format!(
+      "Unsupported mapping key {key:?}"
+    ))),
+  }
+}
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_parameter_file() {
+  use crate::ParameterValue;
+
+  let file = ParameterFile::parse(
+    r#"
+# Comment line
+/**:
+  ros__parameters:
+    use_sim_time: true
+/ns/my_node:
+  ros__parameters:
+    speed: 1.5   # comment
+    name: "some # thing"
+    limits:
+      max: 10
+    waypoints: [1, 2, 3]
+    names:
+    - alpha
+    - beta
+    gains: [1,
+      2.5]
+    enabled: yes
+ns:
+  other_node:
+    ros__parameters:
+      speed: 2
+"#,
+  )
+  .unwrap();
+
+  let my_node = NodeName::new("/ns", "my_node").unwrap();
+  let params = file.parameters_for(&my_node);
+  let names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+  assert_eq!(
+    names,
+    [
+      "use_sim_time",
+      "speed",
+      "name",
+      "limits.max",
+      "waypoints",
+      "names",
+      "gains",
+      "enabled"
+    ]
+  );
+  assert!(matches!(params[0].value, ParameterValue::Boolean(true)));
+  assert!(matches!(params[2].value, ParameterValue::String(ref s) if s == "some # thing"));
+  assert!(matches!(params[3].value, ParameterValue::Integer(10)));
+  assert!(matches!(params[5].value, ParameterValue::StringArray(ref a) if a.len() == 2));
+  assert!(matches!(params[6].value, ParameterValue::DoubleArray(ref a) if a == &[1.0, 2.5]));
+  // Node decides if this is a boolean, by the type of the parameter.
+  assert!(matches!(params[7].value, ParameterValue::String(ref s) if s == "yes"));
+
+  let other_node = NodeName::new("/ns", "other_node").unwrap();
+  assert_eq!(file.parameters_for(&other_node).len(), 2);
+  let elsewhere = NodeName::new("/", "my_node").unwrap();
+  assert_eq!(file.parameters_for(&elsewhere).len(), 1);
+
+  assert!(ParameterFile::parse("").unwrap().sections.is_empty());
+  assert!(matches!(
+    ParameterFile::parse("/**:\n  ros__parameters:\n    a: [1, x]\n"),
+    Err(ParameterFileError::Invalid(_))
+  ));
+  assert!(matches!(
+    ParameterFile::parse("/**:\n  ros__parameters:\n    a: [[1], [2]]\n"),
+    Err(ParameterFileError::Invalid(_))
+  ));
+  assert!(matches!(
+    ParameterFile::parse("/**:\n  ros__parameters:\n    a: 1\n     b: 2\n"),
+    Err(ParameterFileError::Syntax { line: Some(4), .. })
+  ));
+}
+
+#[test]
+fn test_node_name_wildcards() {
+  let m = |pattern: &str, name: &str| {
+    let p: Vec<&str> = pattern.split('/').filter(|t| !t.is_empty()).collect();
+    let n: Vec<&str> = name.split('/').filter(|t| !t.is_empty()).collect();
+    node_name_matches(&p, &n)
+  };
+  assert!(m("/**", "/a/b/node"));
+  assert!(m("/**/node", "/node"));
+  assert!(m("/**/node", "/a/b/node"));
+  assert!(m("/a/*", "/a/node"));
+  assert!(!m("/a/*", "/a/b/node"));
+  assert!(!m("/a/node", "/b/node"));
+}
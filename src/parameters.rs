@@ -1,7 +1,8 @@
 //! Rust-like representation of ROS2 Parameters
 //!
 //! Parameters are key-value paris that can be set in application code, on the
-//! command line (not yet implemented), from environment variables (not
+//! command line (`--ros-args -p name:=value`), from parameter files (see
+//! [`parameter_file`](crate::parameter_file)), from environment variables (not
 //! implemented), or remotely.
 //!
 //! Paramters can be queried and set remotely using e.g. the `ros2 param` or
@@ -15,6 +16,19 @@ pub struct Parameter {
   pub value: ParameterValue,
 }
 
+/// Notification of a changed [`Parameter`] value.
+///
+/// See [`Node::parameter_change_stream`](crate::Node::parameter_change_stream).
+#[derive(Debug, Clone)]
+pub struct ParameterChange {
+  pub name: String,
+  /// Value before the change, or `None` if the Parameter was not set.
+  pub old: Option<ParameterValue>,
+  /// New value. [`ParameterValue::NotSet`] means that the Parameter was
+  /// undeclared.
+  pub new: ParameterValue,
+}
+
 /// Rust-like representation of ROS2
 /// [ParameterValue](https://github.com/ros2/rcl_interfaces/blob/master/rcl_interfaces/msg/ParameterValue.msg)
 #[derive(Debug, Clone)]
@@ -33,6 +47,7 @@ pub enum ParameterValue {
 
 /// List of Parameter types supported by ROS 2.
 /// <https://github.com/ros2/rcl_interfaces/blob/humble/rcl_interfaces/msg/ParameterType.msg>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParameterType {
   NotSet = 0,
   Bool = 1,
@@ -68,6 +83,24 @@ impl ParameterValue {
   }
 }
 
+impl From<u8> for ParameterType {
+  // Unknown values map to NotSet
+  fn from(t: u8) -> ParameterType {
+    match t {
+      raw::ParameterType::BOOL => ParameterType::Bool,
+      raw::ParameterType::INTEGER => ParameterType::Integer,
+      raw::ParameterType::DOUBLE => ParameterType::Double,
+      raw::ParameterType::STRING => ParameterType::String,
+      raw::ParameterType::BYTE_ARRAY => ParameterType::ByteArray,
+      raw::ParameterType::BOOL_ARRAY => ParameterType::BoolArray,
+      raw::ParameterType::INTEGER_ARRAY => ParameterType::IntegerArray,
+      raw::ParameterType::DOUBLE_ARRAY => ParameterType::DoubleArray,
+      raw::ParameterType::STRING_ARRAY => ParameterType::StringArray,
+      _ => ParameterType::NotSet,
+    }
+  }
+}
+
 impl From<raw::Parameter> for Parameter {
   fn from(rp: raw::Parameter) -> Self {
     Parameter {
@@ -186,7 +219,21 @@ impl From<SetParametersResult> for raw::SetParametersResult {
   }
 }
 
+impl From<raw::SetParametersResult> for SetParametersResult {
+  fn from(r: raw::SetParametersResult) -> SetParametersResult {
+    if r.successful {
+      Ok(())
+    } else {
+      Err(r.reason)
+    }
+  }
+}
+
 /// Documentation and constraints for a [`Parameter`]
+///
+/// Constraints are enforced when a Parameter is set, either locally or via
+/// Parameter Services.
+#[derive(Debug, Clone)]
 pub struct ParameterDescriptor {
   pub name: String,
   pub param_type: ParameterType, // ParameterType.msg defines enum
@@ -216,16 +263,117 @@ impl ParameterDescriptor {
     ParameterDescriptor {
       name: name.to_string(),
       param_type: value.to_parameter_type(),
-      description: "(description missing, not implemented)".to_string(),
+      description: "".to_string(),
       additional_constraints: "".to_string(),
       read_only: false,
       dynamic_typing: false,
       range: NumericRange::NotSpecified,
     }
   }
+
+  /// Check if a new value is acceptable according to this descriptor.
+  ///
+  /// `current` is the current value of the Parameter, or `None` if the
+  /// Parameter is being initialized. Read-only Parameters can only be
+  /// initialized.
+  pub fn validate(
+    &self,
+    current: Option<&ParameterValue>,
+    value: &ParameterValue,
+  ) -> SetParametersResult {
+    if self.read_only && current.is_some() {
+      return Err(format!("Parameter '{}' is read-only.", self.name));
+    }
+
+    // Type is fixed by the current value, or by the declared type.
+    let expected_type = match current.map(ParameterValue::to_parameter_type) {
+      Some(ParameterType::NotSet) | None => self.param_type,
+      Some(t) => t,
+    };
+    let value_type = value.to_parameter_type();
+    if !self.dynamic_typing && expected_type != ParameterType::NotSet && value_type != expected_type
+    {
+      return Err(format!(
+        "Parameter '{}' has type {expected_type:?}. Cannot change it to {value_type:?}.",
+        self.name
+      ));
+    }
+
+    match (&self.range, value) {
+      (
+        NumericRange::IntegerRange {
+          from_value,
+          to_value,
+          step,
+        },
+        ParameterValue::Integer(v),
+      ) => {
+        if *step < 0 {
+          Err(format!(
+            "Parameter '{}' has invalid descriptor: negative step {step}.",
+            self.name
+          ))
+        } else if v < from_value || v > to_value {
+          Err(format!(
+            "Parameter '{}' value {v} is not within range [{from_value}, {to_value}].",
+            self.name
+          ))
+        } else if *step != 0
+          && v != to_value
+          && (i128::from(*v) - i128::from(*from_value)) % i128::from(*step) != 0
+        {
+          Err(format!(
+            "Parameter '{}' value {v} is not a multiple of step {step} from {from_value}.",
+            self.name
+          ))
+        } else {
+          Ok(())
+        }
+      }
+      (
+        NumericRange::FloatingPointRange {
+          from_value,
+          to_value,
+          step,
+        },
+        ParameterValue::Double(v),
+      ) => {
+        let nearly_equal = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+        let on_step = || {
+          let nearest = from_value + ((v - from_value) / step).round() * step;
+          nearly_equal(*v, nearest)
+        };
+        if *step < 0.0 {
+          Err(format!(
+            "Parameter '{}' has invalid descriptor: negative step {step}.",
+            self.name
+          ))
+        } else if (v < from_value || v > to_value)
+          && !nearly_equal(*v, *from_value)
+          && !nearly_equal(*v, *to_value)
+        {
+          Err(format!(
+            "Parameter '{}' value {v} is not within range [{from_value}, {to_value}].",
+            self.name
+          ))
+        } else if *step != 0.0 && !nearly_equal(*v, *to_value) && !on_step() {
+          Err(format!(
+            "Parameter '{}' value {v} is not a multiple of step {step} from {from_value}.",
+            self.name
+          ))
+        } else {
+          Ok(())
+        }
+      }
+      _ => Ok(()), // No range, or not applicable to value type.
+    }
+  }
 }
 
 /// Optional Limits for a numeric [`Parameter`]
+///
+/// A `step` of zero means that any value within the range is allowed.
+#[derive(Debug, Clone)]
 pub enum NumericRange {
   NotSpecified,
   IntegerRange {
@@ -285,6 +433,175 @@ impl From<ParameterDescriptor> for raw::ParameterDescriptor {
   }
 }
 
+impl From<raw::ParameterDescriptor> for ParameterDescriptor {
+  // ROS 2 allows at most one range, so any others are ignored.
+  fn from(p: raw::ParameterDescriptor) -> ParameterDescriptor {
+    let range = match (p.integer_range.first(), p.floating_point_range.first()) {
+      (Some(r), _) => NumericRange::IntegerRange {
+        from_value: r.from_value,
+        to_value: r.to_value,
+        step: r.step,
+      },
+      (None, Some(r)) => NumericRange::FloatingPointRange {
+        from_value: r.from_value,
+        to_value: r.to_value,
+        step: r.step,
+      },
+      (None, None) => NumericRange::NotSpecified,
+    };
+    ParameterDescriptor {
+      name: p.name,
+      param_type: p.r#type.into(),
+      description: p.description,
+      additional_constraints: p.additional_constraints,
+      read_only: p.read_only,
+      dynamic_typing: p.dynamic_typing,
+      range,
+    }
+  }
+}
+
+/// Interpret a parameter value written in YAML syntax, e.g. `true`, `42`,
+/// `1.5`, `hello` or `[1, 2, 3]`.
+pub(crate) fn parse_parameter_value(s: &str) -> Result<ParameterValue, String> {
+  let yaml: serde_yaml::Value = serde_yaml::from_str(s).map_err(|e| e.to_string())?;
+  parameter_value_from_yaml(&yaml)
+}
+
+/// Convert a YAML scalar or a sequence of scalars to a ParameterValue.
+///
+/// Sequences must be homogeneous, except that integers are promoted to
+/// doubles. The YAML 1.1 booleans `yes`, `on`, `y` etc. are left as strings,
+/// because serde_yaml does not tell if they were quoted. See
+/// [`resolve_yaml_booleans`].
+pub(crate) fn parameter_value_from_yaml(
+  yaml: &serde_yaml::Value,
+) -> Result<ParameterValue, String> {
+  let items = match yaml {
+    serde_yaml::Value::Sequence(items) => items,
+    scalar => return scalar_from_yaml(scalar),
+  };
+  let mut items = items
+    .iter()
+    .map(scalar_from_yaml)
+    .collect::<Result<Vec<_>, _>>()?;
+  // In a sequence with real booleans, the YAML 1.1 words must be booleans too.
+  if items
+    .iter()
+    .any(|i| matches!(i, ParameterValue::Boolean(_)))
+  {
+    for item in items.iter_mut() {
+      if let ParameterValue::String(s) = item {
+        if let Some(b) = yaml_bool(s) {
+          *item = ParameterValue::Boolean(b);
+        }
+      }
+    }
+  }
+  if items.is_empty() {
+    Err("Cannot determine type of an empty array.".to_owned())
+  } else if items
+    .iter()
+    .all(|i| matches!(i, ParameterValue::Boolean(_)))
+  {
+    Ok(ParameterValue::BooleanArray(
+      items
+        .iter()
+        .filter_map(|i| match i {
+          ParameterValue::Boolean(b) => Some(*b),
+          _ => None,
+        })
+        .collect(),
+    ))
+  } else if items
+    .iter()
+    .all(|i| matches!(i, ParameterValue::Integer(_)))
+  {
+    Ok(ParameterValue::IntegerArray(
+      items
+        .iter()
+        .filter_map(|i| match i {
+          ParameterValue::Integer(i) => Some(*i),
+          _ => None,
+        })
+        .collect(),
+    ))
+  } else if items
+    .iter()
+    .all(|i| matches!(i, ParameterValue::Integer(_) | ParameterValue::Double(_)))
+  {
+    Ok(ParameterValue::DoubleArray(
+      items
+        .iter()
+        .filter_map(|i| match i {
+          ParameterValue::Integer(i) => Some(*i as f64),
+          ParameterValue::Double(d) => Some(*d),
+          _ => None,
+        })
+        .collect(),
+    ))
+  } else if items.iter().all(|i| matches!(i, ParameterValue::String(_))) {
+    Ok(ParameterValue::StringArray(
+      items
+        .into_iter()
+        .filter_map(|i| match i {
+          ParameterValue::String(s) => Some(s),
+          _ => None,
+        })
+        .collect(),
+    ))
+  } else {
+    Err("Array elements must all be of the same type.".to_owned())
+  }
+}
+
+fn scalar_from_yaml(yaml: &serde_yaml::Value) -> Result<ParameterValue, String> {
+  use serde_yaml::Value;
+  match yaml {
+    Value::Bool(b) => Ok(ParameterValue::Boolean(*b)),
+    Value::Number(n) if n.is_f64() => Ok(ParameterValue::Double(n.as_f64().unwrap_or_default())),
+    Value::Number(n) => n
+      .as_i64()
+      .map(ParameterValue::Integer)
+      .ok_or_else(|| format!("Integer {n} is out of range.")),
+    Value::String(s) => Ok(ParameterValue::String(s.clone())),
+    Value::Null => Err("Missing value.".to_owned()),
+    Value::Sequence(_) => Err("Nested arrays are not supported.".to_owned()),
+    Value::Mapping(_) | Value::Tagged(_) => Err("Expected a value or an array.".to_owned()),
+  }
+}
+
+// YAML 1.1 boolean words, which YAML 1.2 parsers read as strings
+fn yaml_bool(s: &str) -> Option<bool> {
+  match s {
+    "y" | "Y" | "yes" | "Yes" | "YES" | "on" | "On" | "ON" => Some(true),
+    "n" | "N" | "no" | "No" | "NO" | "off" | "Off" | "OFF" => Some(false),
+    _ => None,
+  }
+}
+
+/// Like ROS 2, read the YAML 1.1 booleans `yes`, `on`, `y` etc. as booleans,
+/// unless the parameter is expected to be a string.
+///
+/// serde_yaml does not tell quoted strings apart, so `"off"` is a boolean
+/// too, if the parameter is not declared or described as a string.
+pub(crate) fn resolve_yaml_booleans(
+  value: ParameterValue,
+  expected: ParameterType,
+) -> ParameterValue {
+  match (value, expected) {
+    (value, ParameterType::String) | (value, ParameterType::StringArray) => value,
+    (ParameterValue::String(s), _) => match yaml_bool(&s) {
+      Some(b) => ParameterValue::Boolean(b),
+      None => ParameterValue::String(s),
+    },
+    (ParameterValue::StringArray(a), _) if a.iter().all(|s| yaml_bool(s).is_some()) => {
+      ParameterValue::BooleanArray(a.iter().filter_map(|s| yaml_bool(s)).collect())
+    }
+    (value, _) => value,
+  }
+}
+
 /// Raw, ROS2-compatible Parameters for sending over the wire.
 /// Not for use in a Rust application.
 pub mod raw {
@@ -380,3 +697,104 @@ pub mod raw {
     pub step: f64,
   }
 }
+
+// -------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------
+
+#[test]
+fn test_parse_parameter_value() {
+  use ParameterValue::*;
+  assert!(matches!(parse_parameter_value("true"), Ok(Boolean(true))));
+  assert!(matches!(parse_parameter_value("-12"), Ok(Integer(-12))));
+  assert!(matches!(parse_parameter_value("1e3"), Ok(Double(d)) if d == 1000.0));
+  assert!(matches!(parse_parameter_value("nan"), Ok(String(s)) if s == "nan"));
+  assert!(matches!(parse_parameter_value("'42'"), Ok(String(s)) if s == "42"));
+  assert!(matches!(parse_parameter_value("[1, 2]"), Ok(IntegerArray(a)) if a == vec![1, 2]));
+  assert!(matches!(parse_parameter_value("[1, 2.5]"), Ok(DoubleArray(a)) if a == vec![1.0, 2.5]));
+  assert!(matches!(parse_parameter_value("[a, 'b,c']"), Ok(StringArray(a)) if a[1] == "b,c"));
+  assert!(parse_parameter_value("[]").is_err());
+  assert!(parse_parameter_value("[1, x]").is_err());
+  assert!(matches!(parse_parameter_value("on"), Ok(String(s)) if s == "on"));
+  assert!(
+    matches!(parse_parameter_value("[true, no]"), Ok(BooleanArray(a)) if a == vec![true, false])
+  );
+  assert!(matches!(parse_parameter_value("-.inf"), Ok(Double(d)) if d == f64::NEG_INFINITY));
+  assert!(parse_parameter_value("[[1], [2]]").is_err());
+  assert!(parse_parameter_value("{a: 1}").is_err());
+  assert!(parse_parameter_value("").is_err());
+}
+
+#[test]
+fn test_resolve_yaml_booleans() {
+  use ParameterValue::*;
+  let resolve =
+    |s: &str, expected| resolve_yaml_booleans(parse_parameter_value(s).unwrap(), expected);
+  assert!(matches!(
+    resolve("on", ParameterType::NotSet),
+    Boolean(true)
+  ));
+  assert!(matches!(
+    resolve("\"off\"", ParameterType::Bool),
+    Boolean(false)
+  ));
+  assert!(matches!(resolve("\"off\"", ParameterType::String), String(s) if s == "off"));
+  assert!(matches!(resolve("maybe", ParameterType::NotSet), String(s) if s == "maybe"));
+  assert!(
+    matches!(resolve("[yes, No]", ParameterType::NotSet), BooleanArray(a) if a == vec![true, false])
+  );
+  assert!(
+    matches!(resolve("[yes, No]", ParameterType::StringArray), StringArray(a) if a[0] == "yes")
+  );
+}
+
+#[test]
+fn test_parameter_descriptor_validate() {
+  use ParameterValue::*;
+  let mut d = ParameterDescriptor::from_value("speed", &Integer(0));
+  d.range = NumericRange::IntegerRange {
+    from_value: 0,
+    to_value: 9,
+    step: 2,
+  };
+  assert!(d.validate(Some(&Integer(0)), &Integer(4)).is_ok());
+  assert!(d.validate(Some(&Integer(0)), &Integer(9)).is_ok()); // upper limit
+  assert!(d.validate(Some(&Integer(0)), &Integer(3)).is_err()); // step
+  assert!(d.validate(Some(&Integer(0)), &Integer(10)).is_err()); // range
+  assert!(d.validate(Some(&Integer(0)), &Double(4.0)).is_err()); // type
+  d.dynamic_typing = true;
+  assert!(d.validate(Some(&Integer(0)), &Double(4.0)).is_ok());
+  d.range = NumericRange::IntegerRange {
+    from_value: i64::MIN,
+    to_value: i64::MAX,
+    step: 3,
+  };
+  assert!(d.validate(None, &Integer(1)).is_ok()); // 1 - i64::MIN = 2^63 + 1 does not overflow
+  assert!(d.validate(None, &Integer(2)).is_err());
+  d.range = NumericRange::IntegerRange {
+    from_value: 0,
+    to_value: 9,
+    step: -2,
+  };
+  assert!(d.validate(None, &Integer(4)).is_err()); // invalid descriptor
+
+  let mut d = ParameterDescriptor::from_value("gain", &Double(0.0));
+  d.range = NumericRange::FloatingPointRange {
+    from_value: 0.0,
+    to_value: 1.0,
+    step: 0.1,
+  };
+  assert!(d.validate(None, &Double(0.3)).is_ok());
+  assert!(d.validate(None, &Double(0.35)).is_err());
+  assert!(d.validate(None, &Double(1.5)).is_err());
+
+  d.read_only = true;
+  assert!(d.validate(None, &Double(0.5)).is_ok());
+  assert!(d.validate(Some(&Double(0.5)), &Double(0.5)).is_err());
+
+  d.range = NumericRange::FloatingPointRange {
+    from_value: 0.0,
+    to_value: 1.0,
+    step: -0.1,
+  };
+  assert!(d.validate(None, &Double(0.3)).is_err()); // invalid descriptor
+}
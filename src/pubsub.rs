@@ -1,6 +1,8 @@
 use std::{io, marker::PhantomData};
 
 use mio::{Evented, Poll, PollOpt, Ready, Token};
+use async_channel::Receiver;
+use bytes::Bytes;
 use futures::{
   pin_mut,
   stream::{FusedStream, StreamExt},
@@ -13,7 +15,12 @@ use rustdds::{
 };
 use serde::{de::DeserializeOwned, Serialize};
 
-use super::{gid::Gid, message_info::MessageInfo, node::Node};
+use super::{
+  gid::Gid,
+  message_info::MessageInfo,
+  node::{wait_timeout, DiscoveryError, EndpointRegistration, MatchEvent, Node},
+  statistics::TopicStatistics,
+};
 
 /// A ROS2 Publisher
 ///
@@ -21,25 +28,47 @@ use super::{gid::Gid, message_info::MessageInfo, node::Node};
 /// DDS
 pub struct Publisher<M: Serialize> {
   datawriter: no_key::DataWriterCdr<M>,
+  // Present, if created via a Node
+  _registration: Option<EndpointRegistration>,
 }
 
 impl<M: Serialize> Publisher<M> {
   // These must be created from Node
   pub(crate) fn new(datawriter: no_key::DataWriterCdr<M>) -> Publisher<M> {
-    Publisher { datawriter }
+    Publisher {
+      datawriter,
+      _registration: None,
+    }
+  }
+
+  pub(crate) fn set_registration(&mut self, registration: EndpointRegistration) {
+    self._registration = Some(registration);
   }
 
   pub fn publish(&self, message: M) -> WriteResult<(), M> {
     self.datawriter.write(message, Some(Timestamp::now()))
   }
 
-  // pub(crate) fn publish_with_options(
-  //   &self,
-  //   message: M,
-  //   wo: WriteOptions,
-  // ) -> dds::Result<rustdds::rpc::SampleIdentity> {
-  //   self.datawriter.write_with_options(message, wo)
-  // }
+  /// Publish with the given source timestamp instead of the current time,
+  /// e.g. sensor capture time or simulation time.
+  pub fn publish_at(&self, message: M, source_timestamp: Timestamp) -> WriteResult<(), M> {
+    self.datawriter.write(message, Some(source_timestamp))
+  }
+
+  /// Publish with explicit [`WriteOptions`], e.g. source timestamp and
+  /// related sample identity. Use [`WriteOptionsBuilder`] to construct these.
+  ///
+  /// Note that unlike [`Self::publish`], this does not set the source
+  /// timestamp, unless it is given in `options`.
+  ///
+  /// Returns the identity of the published sample.
+  pub fn publish_with_options(
+    &self,
+    message: M,
+    options: WriteOptions,
+  ) -> WriteResult<rustdds::rpc::SampleIdentity, M> {
+    self.datawriter.write_with_options(message, options)
+  }
 
   pub fn assert_liveliness(&self) -> WriteResult<(), ()> {
     self.datawriter.assert_liveliness()
@@ -61,15 +90,38 @@ impl<M: Serialize> Publisher<M> {
     my_node.get_subscription_count(self.guid())
   }
 
+  /// Stream of Subscriptions being matched and unmatched with this Publisher.
+  /// Current matches are reported first. A match is reported again, if its
+  /// owning Node becomes known later.
+  ///
+  /// `my_node` must be the Node that created this Publisher, and have a
+  /// Spinner running.
+  pub fn matched_events(&self, my_node: &Node) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    my_node.subscription_matched_events(self.guid())
+  }
+
   /// Waits until there is at least one matched subscription on this topic,
   /// possibly forever.
   ///
   /// `my_node` must be the Node that created this Subscription, or the length
-  /// of the wait is undefined.
-  pub fn wait_for_subscription(&self, my_node: &Node) -> impl Future<Output = ()> + Send {
+  /// of the wait is undefined. Fails, if `my_node` has no Spinner running.
+  pub fn wait_for_subscription(
+    &self,
+    my_node: &Node,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
     my_node.wait_for_reader(self.guid())
   }
 
+  /// Like [`Self::wait_for_subscription`], but fails with
+  /// [`DiscoveryError::Timeout`] if there is no match within `timeout`.
+  pub fn wait_for_subscription_timeout(
+    &self,
+    my_node: &Node,
+    timeout: std::time::Duration,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
+    wait_timeout(my_node.wait_for_reader(self.guid()), timeout)
+  }
+
   pub async fn async_publish(&self, message: M) -> WriteResult<(), M> {
     self
       .datawriter
@@ -77,13 +129,43 @@ impl<M: Serialize> Publisher<M> {
       .await
   }
 
-  #[allow(dead_code)] // This is for async Service implementation. Remove this when it is implemented.
-  pub(crate) async fn async_publish_with_options(
+  /// Async version of [`Self::publish_at`]
+  pub async fn async_publish_at(
     &self,
     message: M,
-    wo: WriteOptions,
-  ) -> dds::WriteResult<rustdds::rpc::SampleIdentity, M> {
-    self.datawriter.async_write_with_options(message, wo).await
+    source_timestamp: Timestamp,
+  ) -> WriteResult<(), M> {
+    self
+      .datawriter
+      .async_write(message, Some(source_timestamp))
+      .await
+  }
+
+  /// Async version of [`Self::publish_with_options`]
+  pub async fn async_publish_with_options(
+    &self,
+    message: M,
+    options: WriteOptions,
+  ) -> WriteResult<rustdds::rpc::SampleIdentity, M> {
+    self
+      .datawriter
+      .async_write_with_options(message, options)
+      .await
+  }
+}
+
+impl Publisher<SerializedMessage> {
+  /// Publish an already serialized message, e.g. one received from a
+  /// [`RawSubscription`]. The payload must be CDR little-endian encoded and
+  /// match the type of the Topic.
+  pub fn publish_serialized(
+    &self,
+    payload: impl Into<Bytes>,
+  ) -> WriteResult<(), SerializedMessage> {
+    self.publish(SerializedMessage::new(
+      RepresentationIdentifier::CDR_LE,
+      payload,
+    ))
   }
 }
 // ----------------------------------------------------
@@ -98,6 +180,9 @@ impl<M: Serialize> Publisher<M> {
 /// DDS
 pub struct Subscription<M> {
   datareader: no_key::SimpleDataReaderCdr<M>,
+  // Present, if created via a Node
+  _registration: Option<EndpointRegistration>,
+  statistics: Option<TopicStatistics>,
 }
 
 impl<M> Subscription<M>
@@ -106,7 +191,40 @@ where
 {
   // These must be created from Node
   pub(crate) fn new(datareader: no_key::SimpleDataReaderCdr<M>) -> Subscription<M> {
-    Subscription { datareader }
+    Subscription {
+      datareader,
+      _registration: None,
+      statistics: None,
+    }
+  }
+
+  pub(crate) fn set_registration(&mut self, registration: EndpointRegistration) {
+    self._registration = Some(registration);
+  }
+
+  /// Start collecting statistics of received messages. Returns a handle for
+  /// reading the statistics. Calling this again returns the same collector.
+  ///
+  /// Only this Subscription type collects statistics. [`RawSubscription`] and
+  /// [`DynamicSubscription`](crate::dynamic::DynamicSubscription) do not.
+  pub fn enable_statistics(&mut self) -> TopicStatistics {
+    self
+      .statistics
+      .get_or_insert_with(TopicStatistics::new)
+      .clone()
+  }
+
+  /// Statistics collector, if enabled
+  pub fn statistics(&self) -> Option<&TopicStatistics> {
+    self.statistics.as_ref()
+  }
+
+  fn received(&self, dcc: no_key::DeserializedCacheChange<M>) -> (M, MessageInfo) {
+    let (value, info) = dcc_to_value_and_messageinfo(dcc);
+    if let Some(statistics) = &self.statistics {
+      statistics.record(&info);
+    }
+    (value, info)
   }
 
   pub fn take_seed<'de, S>(&self, seed: S) -> ReadResult<Option<(M, MessageInfo)>>
@@ -118,7 +236,7 @@ where
     let decoder = CdrDeserializeSeedDecoder::new(seed, PhantomData::<()>);
     let ds: Option<no_key::DeserializedCacheChange<M>> =
       self.datareader.try_take_one_with(decoder)?;
-    Ok(ds.map(dcc_to_value_and_messageinfo))
+    Ok(ds.map(|dcc| self.received(dcc)))
   }
 
   // Returns an async Stream of messages with MessageInfo metadata
@@ -134,7 +252,7 @@ where
     self
       .datareader
       .as_async_stream_with(decoder)
-      .map(|result| result.map(dcc_to_value_and_messageinfo))
+      .map(move |result| result.map(|dcc| self.received(dcc)))
   }
 }
 
@@ -142,7 +260,7 @@ impl<M: 'static + DeserializeOwned> Subscription<M> {
   pub fn take(&self) -> ReadResult<Option<(M, MessageInfo)>> {
     self.datareader.drain_read_notifications();
     let ds: Option<no_key::DeserializedCacheChange<M>> = self.datareader.try_take_one()?;
-    Ok(ds.map(dcc_to_value_and_messageinfo))
+    Ok(ds.map(|dcc| self.received(dcc)))
   }
 
   pub async fn async_take(&self) -> ReadResult<(M, MessageInfo)> {
@@ -150,7 +268,7 @@ impl<M: 'static + DeserializeOwned> Subscription<M> {
     pin_mut!(async_stream);
     match async_stream.next().await {
       Some(Err(e)) => Err(e),
-      Some(Ok(ds)) => Ok(dcc_to_value_and_messageinfo(ds)),
+      Some(Ok(ds)) => Ok(self.received(ds)),
       // Stream from SimpleDataReader is not supposed to ever end.
       None => {
         read_error_internal!("async_take(): SimpleDataReader value stream unexpectedly ended!")
@@ -163,7 +281,7 @@ impl<M: 'static + DeserializeOwned> Subscription<M> {
     self
       .datareader
       .as_async_stream()
-      .map(|result| result.map(dcc_to_value_and_messageinfo))
+      .map(move |result| result.map(|dcc| self.received(dcc)))
   }
 }
 
@@ -187,14 +305,228 @@ where
     my_node.get_publisher_count(self.guid())
   }
 
+  /// Stream of Publishers being matched and unmatched with this Subscription.
+  /// Current matches are reported first. A match is reported again, if its
+  /// owning Node becomes known later.
+  ///
+  /// `my_node` must be the Node that created this Subscription, and have a
+  /// Spinner running.
+  pub fn matched_events(&self, my_node: &Node) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    my_node.publisher_matched_events(self.guid())
+  }
+
   /// Waits until there is at least one matched publisher on this topic,
   /// possibly forever.
   ///
   /// `my_node` must be the Node that created this Subscription, or the length
-  /// of the wait is undefined.
-  pub fn wait_for_publisher(&self, my_node: &Node) -> impl Future<Output = ()> + Send {
+  /// of the wait is undefined. Fails, if `my_node` has no Spinner running.
+  pub fn wait_for_publisher(
+    &self,
+    my_node: &Node,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
+    my_node.wait_for_writer(self.guid())
+  }
+
+  /// Like [`Self::wait_for_publisher`], but fails with
+  /// [`DiscoveryError::Timeout`] if there is no match within `timeout`.
+  pub fn wait_for_publisher_timeout(
+    &self,
+    my_node: &Node,
+    timeout: std::time::Duration,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
+    wait_timeout(my_node.wait_for_writer(self.guid()), timeout)
+  }
+}
+
+// ----------------------------------------------------
+// ----------------------------------------------------
+
+/// A message in serialized form, i.e. the payload of a DDS sample and its
+/// encoding, without knowing the message type.
+///
+/// Received from a [`RawSubscription`]. To publish serialized messages,
+/// create a `Publisher<SerializedMessage>` and use
+/// [`Publisher::publish_serialized`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializedMessage {
+  representation_identifier: RepresentationIdentifier,
+  payload: Bytes,
+}
+
+impl SerializedMessage {
+  pub fn new(
+    representation_identifier: RepresentationIdentifier,
+    payload: impl Into<Bytes>,
+  ) -> SerializedMessage {
+    SerializedMessage {
+      representation_identifier,
+      payload: payload.into(),
+    }
+  }
+
+  /// Encoding of the payload. Always [`RepresentationIdentifier::CDR_LE`]
+  /// in messages from a [`RawSubscription`].
+  pub fn representation_identifier(&self) -> RepresentationIdentifier {
+    self.representation_identifier
+  }
+
+  /// Serialized message, without the encapsulation header
+  pub fn payload(&self) -> &Bytes {
+    &self.payload
+  }
+
+  pub fn into_payload(self) -> Bytes {
+    self.payload
+  }
+}
+
+// Publishers always produce CDR_LE. The CDR serializer writes bytes as-is,
+// without a length prefix, so this produces the payload unchanged.
+impl Serialize for SerializedMessage {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    if self.representation_identifier == RepresentationIdentifier::CDR_LE {
+      serializer.serialize_bytes(&self.payload)
+    } else {
+      Err(serde::ser::Error::custom(format!(
+        "Cannot publish {:?} payload, only CDR_LE",
+        self.representation_identifier
+      )))
+    }
+  }
+}
+
+/// A Subscription that does not deserialize messages, but gives them as
+/// [`SerializedMessage`]s.
+///
+/// This is similar to receiving `SerializedMessage` in rclcpp, and is useful
+/// for e.g. recording or relaying topics without knowing their types.
+///
+/// Only CDR little-endian samples are received, so that they can be published
+/// again as they are. Samples in other encodings are reported as read errors.
+pub struct RawSubscription {
+  datareader: no_key::SimpleDataReader<SerializedMessage, RawDeserializerAdapter>,
+  _registration: EndpointRegistration,
+}
+
+impl RawSubscription {
+  pub(crate) fn new(
+    datareader: no_key::SimpleDataReader<SerializedMessage, RawDeserializerAdapter>,
+    registration: EndpointRegistration,
+  ) -> RawSubscription {
+    RawSubscription {
+      datareader,
+      _registration: registration,
+    }
+  }
+
+  pub fn take(&self) -> ReadResult<Option<(SerializedMessage, MessageInfo)>> {
+    self.datareader.drain_read_notifications();
+    let ds = self.datareader.try_take_one()?;
+    Ok(ds.map(dcc_to_value_and_messageinfo))
+  }
+
+  pub async fn async_take(&self) -> ReadResult<(SerializedMessage, MessageInfo)> {
+    let async_stream = self.datareader.as_async_stream();
+    pin_mut!(async_stream);
+    match async_stream.next().await {
+      Some(Err(e)) => Err(e),
+      Some(Ok(ds)) => Ok(dcc_to_value_and_messageinfo(ds)),
+      // Stream from SimpleDataReader is not supposed to ever end.
+      None => {
+        read_error_internal!("async_take(): SimpleDataReader value stream unexpectedly ended!")
+      }
+    }
+  }
+
+  // Returns an async Stream of messages with MessageInfo metadata
+  pub fn async_stream(
+    &self,
+  ) -> impl FusedStream<Item = ReadResult<(SerializedMessage, MessageInfo)>> + '_ {
+    self
+      .datareader
+      .as_async_stream()
+      .map(|result| result.map(dcc_to_value_and_messageinfo))
+  }
+
+  pub fn guid(&self) -> rustdds::GUID {
+    self.datareader.guid()
+  }
+
+  pub fn gid(&self) -> Gid {
+    self.guid().into()
+  }
+
+  /// See [`Subscription::get_publisher_count`]
+  pub fn get_publisher_count(&self, my_node: &Node) -> usize {
+    my_node.get_publisher_count(self.guid())
+  }
+
+  /// See [`Subscription::matched_events`]
+  pub fn matched_events(&self, my_node: &Node) -> Result<Receiver<MatchEvent>, DiscoveryError> {
+    my_node.publisher_matched_events(self.guid())
+  }
+
+  /// See [`Subscription::wait_for_publisher`]
+  pub fn wait_for_publisher(
+    &self,
+    my_node: &Node,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
     my_node.wait_for_writer(self.guid())
   }
+
+  /// See [`Subscription::wait_for_publisher_timeout`]
+  pub fn wait_for_publisher_timeout(
+    &self,
+    my_node: &Node,
+    timeout: std::time::Duration,
+  ) -> impl Future<Output = Result<(), DiscoveryError>> + Send {
+    wait_timeout(my_node.wait_for_writer(self.guid()), timeout)
+  }
+}
+
+// Pass-through adapter: Keeps the payload as it is.
+pub(crate) struct RawDeserializerAdapter;
+
+impl RawDeserializerAdapter {
+  // Publishers can write only CDR_LE, so accept only that. Then everything
+  // received can be relayed.
+  const REPR_IDS: [RepresentationIdentifier; 1] = [RepresentationIdentifier::CDR_LE];
+}
+
+impl no_key::DeserializerAdapter<SerializedMessage> for RawDeserializerAdapter {
+  type Error = ReadError;
+  type Decoded = SerializedMessage;
+
+  fn supported_encodings() -> &'static [RepresentationIdentifier] {
+    &Self::REPR_IDS
+  }
+
+  fn transform_decoded(decoded: SerializedMessage) -> SerializedMessage {
+    decoded
+  }
+}
+
+impl no_key::DefaultDecoder<SerializedMessage> for RawDeserializerAdapter {
+  type Decoder = RawDecoder;
+  const DECODER: Self::Decoder = RawDecoder;
+}
+
+#[derive(Clone)]
+pub(crate) struct RawDecoder;
+
+impl no_key::Decode<SerializedMessage> for RawDecoder {
+  type Error = ReadError;
+
+  fn decode_bytes(
+    self,
+    input_bytes: &[u8],
+    encoding: RepresentationIdentifier,
+  ) -> Result<SerializedMessage, Self::Error> {
+    Ok(SerializedMessage::new(
+      encoding,
+      Bytes::copy_from_slice(input_bytes),
+    ))
+  }
 }
 
 // helper
@@ -228,3 +560,44 @@ where
     self.datareader.deregister(poll)
   }
 }
+
+// ----------------------------------------------------
+// ----------------------------------------------------
+
+#[test]
+fn test_serialized_message_is_published_as_is() {
+  use rustdds::serialization::to_writer_with_rep_id;
+
+  let payload = vec![1, 0, 0, 0, 2, 0, 0, 0, 0x61, 0];
+  let message = SerializedMessage::new(RepresentationIdentifier::CDR_LE, payload.clone());
+  let mut bytes = Vec::new();
+  to_writer_with_rep_id(&mut bytes, &message, RepresentationIdentifier::CDR_LE).unwrap();
+  assert_eq!(bytes, payload);
+
+  let message = SerializedMessage::new(RepresentationIdentifier::CDR_BE, payload);
+  assert!(to_writer_with_rep_id(Vec::new(), &message, RepresentationIdentifier::CDR_LE).is_err());
+}
+
+#[test]
+fn test_raw_message_round_trip() {
+  use rustdds::{no_key::DeserializerAdapter, serialization::to_writer_with_rep_id};
+
+  // Received as-is, and published again as-is
+  let payload = vec![1, 0, 0, 0, 2, 0, 0, 0, 0x61, 0];
+  let message: SerializedMessage =
+    RawDeserializerAdapter::from_bytes(&payload, RepresentationIdentifier::CDR_LE).unwrap();
+  assert_eq!(
+    message.representation_identifier(),
+    RepresentationIdentifier::CDR_LE
+  );
+  assert_eq!(message.payload().as_ref(), payload.as_slice());
+  let mut bytes = Vec::new();
+  to_writer_with_rep_id(&mut bytes, &message, RepresentationIdentifier::CDR_LE).unwrap();
+  assert_eq!(bytes, payload);
+
+  // Nothing is received that could not be published.
+  assert_eq!(
+    RawDeserializerAdapter::supported_encodings(),
+    &[RepresentationIdentifier::CDR_LE]
+  );
+}
@@ -17,9 +17,10 @@ pub type SetParametersService = AService<SetParametersRequest, SetParametersResp
 pub type DescribeParametersService =
   AService<DescribeParametersRequest, DescribeParametersResponse>;
 
-// This is structurally identical to SetParamtersService, but the operation
-// of the service is slightly different.
-pub type SetParametersAtomicallyService = AService<SetParametersRequest, SetParametersResponse>;
+// The request is structurally identical to SetParamtersService, but the
+// response carries only a single result for the whole set.
+pub type SetParametersAtomicallyService =
+  AService<SetParametersRequest, SetParametersAtomicallyResponse>;
 
 #[allow(non_snake_case)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +30,11 @@ pub struct ListParametersRequest {
 }
 impl Message for ListParametersRequest {}
 
+impl ListParametersRequest {
+  /// Value of `depth` to list parameters at any depth.
+  pub const DEPTH_RECURSIVE: u64 = 0;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListParametersResult {
   pub names: Vec<String>,
@@ -78,7 +84,12 @@ pub struct SetParametersResponse {
 }
 impl Message for SetParametersResponse {}
 
-pub type SetParametersAtomicallyResponse = SetParametersResponse;
+// https://github.com/ros2/rcl_interfaces/blob/humble/rcl_interfaces/srv/SetParametersAtomically.srv
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetParametersAtomicallyResponse {
+  pub result: parameters::raw::SetParametersResult,
+}
+impl Message for SetParametersAtomicallyResponse {}
 
 // https://github.com/ros2/rcl_interfaces/blob/humble/rcl_interfaces/srv/DescribeParameters.srv
 #[derive(Debug, Clone, Serialize, Deserialize)]
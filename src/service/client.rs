@@ -10,7 +10,11 @@ use rustdds::{
   *,
 };
 
-use crate::{message_info::MessageInfo, node::Node, service::*};
+use crate::{
+  message_info::MessageInfo,
+  node::{wait_timeout, DiscoveryError, EndpointRegistration, Node},
+  service::*,
+};
 
 /// Client end of a ROS2 Service
 pub struct Client<S>
@@ -24,6 +28,8 @@ where
   response_receiver: SimpleDataReaderR<ResponseWrapper<S::Response>>,
   sequence_number_gen: atomic::AtomicI64, // used by basic and cyclone
   client_guid: GUID,                      // used by the Cyclone ServiceMapping
+  // Remove request_sender and response_receiver from ROS discovery on drop
+  _registrations: [EndpointRegistration; 2],
 }
 
 impl<S> Client<S>
@@ -38,11 +44,11 @@ where
     qos_request: Option<QosPolicies>,
     qos_response: Option<QosPolicies>,
   ) -> CreateResult<Self> {
-    let request_sender =
+    let (request_sender, request_registration) =
       node.create_datawriter
       ::<RequestWrapper<S::Request>, ServiceSerializerAdapter<RequestWrapper<S::Request>>>(
         request_topic, qos_request)?;
-    let response_receiver =
+    let (response_receiver, response_registration) =
       node.create_simpledatareader
       ::<ResponseWrapper<S::Response>, ServiceDeserializerAdapter<ResponseWrapper<S::Response>>>(
         response_topic, qos_response)?;
@@ -59,6 +65,7 @@ where
       response_receiver,
       sequence_number_gen: atomic::AtomicI64::new(SequenceNumber::default().into()),
       client_guid,
+      _registrations: [request_registration, response_registration],
     })
   }
 
@@ -212,12 +219,24 @@ where
   /// It is enough that someone has subscribed the Requests, and someone is
   /// a publisher for Responses.
   ///
-  /// May panic, if the Node does not havea background Spinner running.
-  pub async fn wait_for_service(&self, my_node: &Node) {
-    join!(
+  /// Fails, if the Node does not have a background Spinner running.
+  pub async fn wait_for_service(&self, my_node: &Node) -> Result<(), DiscoveryError> {
+    let (request_result, response_result) = join!(
       my_node.wait_for_reader(self.request_sender.guid()),
       my_node.wait_for_writer(self.response_receiver.guid())
     );
+    request_result.and(response_result)
+  }
+
+  /// Like [`Self::wait_for_service`], but fails with
+  /// [`DiscoveryError::Timeout`] if the Server does not appear within
+  /// `timeout`.
+  pub async fn wait_for_service_timeout(
+    &self,
+    my_node: &Node,
+    timeout: std::time::Duration,
+  ) -> Result<(), DiscoveryError> {
+    wait_timeout(self.wait_for_service(my_node), timeout).await
   }
 
   fn increment_sequence_number(&self) {
@@ -116,3 +116,30 @@ pub enum ServiceMapping {
   ///   over actual network.
   Cyclone,
 }
+
+impl ServiceMapping {
+  /// The mapping used by ROS 2 with the given RMW implementation, e.g.
+  /// `"rmw_cyclonedds_cpp"`. Unknown implementations map to
+  /// [`Enhanced`](ServiceMapping::Enhanced), which is used by the default
+  /// RMW, `rmw_fastrtps_cpp`.
+  pub fn for_rmw_implementation(rmw_implementation: &str) -> ServiceMapping {
+    match rmw_implementation {
+      "rmw_cyclonedds_cpp" => ServiceMapping::Cyclone,
+      _ => ServiceMapping::Enhanced,
+    }
+  }
+
+  /// Detect the mapping from environment variables `RMW_IMPLEMENTATION` and
+  /// `RMW_CONNEXT_REQUEST_REPLY_MAPPING`, like ROS 2 would.
+  pub fn from_env() -> ServiceMapping {
+    let rmw_implementation = std::env::var("RMW_IMPLEMENTATION").unwrap_or_default();
+    let connext_basic = std::env::var("RMW_CONNEXT_REQUEST_REPLY_MAPPING")
+      .map(|m| m == "basic")
+      .unwrap_or(false);
+    if rmw_implementation.starts_with("rmw_connext") && connext_basic {
+      ServiceMapping::Basic
+    } else {
+      Self::for_rmw_implementation(&rmw_implementation)
+    }
+  }
+}
@@ -10,7 +10,11 @@ use rustdds::{
   *,
 };
 
-use crate::{message_info::MessageInfo, node::Node, service::*};
+use crate::{
+  message_info::MessageInfo,
+  node::{EndpointRegistration, Node},
+  service::*,
+};
 
 // --------------------------------------------
 // --------------------------------------------
@@ -24,6 +28,8 @@ where
   service_mapping: ServiceMapping,
   request_receiver: SimpleDataReaderR<RequestWrapper<S::Request>>,
   response_sender: DataWriterR<ResponseWrapper<S::Response>>,
+  // Remove request_receiver and response_sender from ROS discovery on drop
+  _registrations: [EndpointRegistration; 2],
 }
 
 impl<S> Server<S>
@@ -38,11 +44,11 @@ where
     qos_request: Option<QosPolicies>,
     qos_response: Option<QosPolicies>,
   ) -> CreateResult<Self> {
-    let request_receiver =
+    let (request_receiver, request_registration) =
       node.create_simpledatareader
       ::<RequestWrapper<S::Request>, ServiceDeserializerAdapter<RequestWrapper<S::Request>>>(
         request_topic, qos_request)?;
-    let response_sender =
+    let (response_sender, response_registration) =
       node.create_datawriter
       ::<ResponseWrapper<S::Response>, ServiceSerializerAdapter<ResponseWrapper<S::Response>>>(
         response_topic, qos_response)?;
@@ -57,6 +63,7 @@ where
       service_mapping,
       request_receiver,
       response_sender,
+      _registrations: [request_registration, response_registration],
     })
   }
 
@@ -0,0 +1,404 @@
+//! Opt-in statistics of messages received by a [`Subscription`].
+//!
+//! Statistics are kept separately for each publisher: received count, lost,
+//! out-of-order and duplicate messages (from sequence numbers), latency, and
+//! rate.
+//! Additionally, message age and period can be published as
+//! [`MetricsMessage`]s, like ROS 2 topic statistics do.
+//!
+//! See [`Subscription::enable_statistics`].
+//!
+//! [`Subscription`]: crate::Subscription
+//! [`Subscription::enable_statistics`]: crate::Subscription::enable_statistics
+use std::{
+  collections::{BTreeMap, BTreeSet, VecDeque},
+  sync::{Arc, Mutex},
+};
+
+use rustdds::{dds::WriteResult, Timestamp};
+
+use crate::{
+  builtin_interfaces,
+  gid::Gid,
+  message_info::MessageInfo,
+  pubsub::Publisher,
+  statistics_msgs::{MetricsMessage, StatisticDataPoint, StatisticDataType},
+};
+
+// How many latest latencies are kept for computing percentiles
+const LATENCY_SAMPLES: usize = 1024;
+
+// How many latest skipped sequence numbers are remembered, so that their late
+// arrival can be told apart from duplicates.
+const MISSING_SAMPLES: usize = 1024;
+
+/// Statistics of a single publisher, as seen by the Subscription.
+///
+/// Latencies are in milliseconds, from source timestamp to reception. They are
+/// only meaningful, if the clocks of publisher and subscriber are
+/// synchronized, and can be negative otherwise.
+#[derive(Debug, Clone)]
+pub struct PublisherStatistics {
+  pub publisher: Gid,
+  pub received: u64,
+  /// Messages skipped in the sequence numbers, and not received later.
+  pub lost: u64,
+  /// Skipped messages that were received later
+  pub out_of_order: u64,
+  /// Messages received again. Messages arriving very late, after many more
+  /// have been skipped, are also counted here.
+  pub duplicates: u64,
+  /// `None`, if no received message had a source timestamp
+  pub latency: Option<LatencyStatistics>,
+  /// Messages per second, from first to last received message. `None`, if
+  /// fewer than two messages have been received.
+  pub rate: Option<f64>,
+}
+
+/// Latency in milliseconds. Minimum, mean, and maximum are over all
+/// received messages, percentiles over the most recent ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyStatistics {
+  pub min: f64,
+  pub mean: f64,
+  pub max: f64,
+  pub p50: f64,
+  pub p90: f64,
+  pub p99: f64,
+}
+
+/// Statistics collector of a Subscription.
+///
+/// This is a handle that can be cloned and used e.g. from another task,
+/// while the Subscription is used to receive messages.
+#[derive(Clone)]
+pub struct TopicStatistics {
+  inner: Arc<Mutex<StatisticsInner>>,
+}
+
+impl TopicStatistics {
+  pub(crate) fn new() -> TopicStatistics {
+    TopicStatistics {
+      inner: Arc::new(Mutex::new(StatisticsInner {
+        publishers: BTreeMap::new(),
+        window: Window::new(Timestamp::now()),
+        last_received: None,
+      })),
+    }
+  }
+
+  pub(crate) fn record(&self, info: &MessageInfo) {
+    self.inner.lock().unwrap().record(info);
+  }
+
+  /// Current statistics of each publisher that has sent us messages.
+  pub fn publishers(&self) -> Vec<PublisherStatistics> {
+    let inner = self.inner.lock().unwrap();
+    inner
+      .publishers
+      .iter()
+      .map(|(gid, tracker)| tracker.statistics(*gid))
+      .collect()
+  }
+
+  /// Forget all collected statistics.
+  pub fn reset(&self) {
+    let mut inner = self.inner.lock().unwrap();
+    inner.publishers.clear();
+    inner.window = Window::new(Timestamp::now());
+    inner.last_received = None;
+  }
+
+  /// Message age and period over the time window since the previous call,
+  /// as "message_age" and "message_period" metrics. Starts a new window.
+  ///
+  /// `measurement_source_name` is typically the name of the subscribing
+  /// Node.
+  pub fn take_metrics(&self, measurement_source_name: &str) -> Vec<MetricsMessage> {
+    let now = Timestamp::now();
+    let window = std::mem::replace(&mut self.inner.lock().unwrap().window, Window::new(now));
+    let metrics = |metrics_source: &str, aggregate: &Aggregate| MetricsMessage {
+      measurement_source_name: measurement_source_name.to_string(),
+      metrics_source: metrics_source.to_string(),
+      unit: "ms".to_string(),
+      window_start: to_time(window.start),
+      window_stop: to_time(now),
+      statistics: aggregate.data_points(),
+    };
+    vec![
+      metrics("message_age", &window.age),
+      metrics("message_period", &window.period),
+    ]
+  }
+
+  /// Publish [`Self::take_metrics`] every `period`.
+  ///
+  /// ROS 2 topic statistics are published to Topic `/statistics` with type
+  /// `statistics_msgs/MetricsMessage`. This runs until publishing fails.
+  pub async fn publish_metrics(
+    &self,
+    publisher: &Publisher<MetricsMessage>,
+    measurement_source_name: &str,
+    period: std::time::Duration,
+  ) -> WriteResult<(), MetricsMessage> {
+    loop {
+      async_io::Timer::after(period).await;
+      for message in self.take_metrics(measurement_source_name) {
+        publisher.async_publish(message).await?;
+      }
+    }
+  }
+}
+
+struct StatisticsInner {
+  publishers: BTreeMap<Gid, PublisherTracker>,
+  window: Window,
+  last_received: Option<Timestamp>, // from any publisher, for period
+}
+
+impl StatisticsInner {
+  fn record(&mut self, info: &MessageInfo) {
+    let received = info.received_timestamp();
+    let latency = info.source_timestamp().map(|sent| millis(received - sent));
+
+    self
+      .publishers
+      .entry(info.publisher_gid())
+      .or_insert_with(|| PublisherTracker::new(received))
+      .record(i64::from(info.sequence_number()), received, latency);
+
+    if let Some(age) = latency {
+      self.window.age.add(age);
+    }
+    if let Some(previous) = self.last_received.replace(received) {
+      self.window.period.add(millis(received - previous));
+    }
+  }
+}
+
+struct PublisherTracker {
+  received: u64,
+  lost: u64,
+  out_of_order: u64,
+  duplicates: u64,
+  highest_sequence_number: Option<i64>,
+  missing: BTreeSet<i64>, // skipped, but may still arrive
+  first_received: Timestamp,
+  last_received: Timestamp,
+  latency: Aggregate,
+  latest_latencies: VecDeque<f64>,
+}
+
+impl PublisherTracker {
+  fn new(first_received: Timestamp) -> PublisherTracker {
+    PublisherTracker {
+      received: 0,
+      lost: 0,
+      out_of_order: 0,
+      duplicates: 0,
+      highest_sequence_number: None,
+      missing: BTreeSet::new(),
+      first_received,
+      last_received: first_received,
+      latency: Aggregate::default(),
+      latest_latencies: VecDeque::new(),
+    }
+  }
+
+  fn record(&mut self, sequence_number: i64, received: Timestamp, latency: Option<f64>) {
+    self.received += 1;
+    self.last_received = received;
+    match self.highest_sequence_number {
+      Some(highest) if sequence_number <= highest => {
+        if self.missing.remove(&sequence_number) {
+          // Late arrival. It was counted as lost, when it was skipped.
+          self.out_of_order += 1;
+          self.lost -= 1;
+        } else {
+          self.duplicates += 1;
+        }
+      }
+      Some(highest) => {
+        self.lost += (sequence_number - highest - 1) as u64;
+        let first_missing = (highest + 1).max(sequence_number - MISSING_SAMPLES as i64);
+        self.missing.extend(first_missing..sequence_number);
+        while self.missing.len() > MISSING_SAMPLES {
+          self.missing.pop_first();
+        }
+        self.highest_sequence_number = Some(sequence_number);
+      }
+      None => self.highest_sequence_number = Some(sequence_number),
+    }
+    if let Some(latency) = latency {
+      self.latency.add(latency);
+      if self.latest_latencies.len() == LATENCY_SAMPLES {
+        self.latest_latencies.pop_front();
+      }
+      self.latest_latencies.push_back(latency);
+    }
+  }
+
+  fn statistics(&self, publisher: Gid) -> PublisherStatistics {
+    let latency = if self.latency.count > 0 {
+      let mut sorted: Vec<f64> = self.latest_latencies.iter().copied().collect();
+      sorted.sort_by(f64::total_cmp);
+      let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+      Some(LatencyStatistics {
+        min: self.latency.min,
+        mean: self.latency.mean(),
+        max: self.latency.max,
+        p50: percentile(0.5),
+        p90: percentile(0.9),
+        p99: percentile(0.99),
+      })
+    } else {
+      None
+    };
+    let duration = millis(self.last_received - self.first_received) / 1000.0;
+    let rate = if self.received > 1 && duration > 0.0 {
+      Some((self.received - 1) as f64 / duration)
+    } else {
+      None
+    };
+    PublisherStatistics {
+      publisher,
+      received: self.received,
+      lost: self.lost,
+      out_of_order: self.out_of_order,
+      duplicates: self.duplicates,
+      latency,
+      rate,
+    }
+  }
+}
+
+// Time window for MetricsMessages
+struct Window {
+  start: Timestamp,
+  age: Aggregate,
+  period: Aggregate,
+}
+
+impl Window {
+  fn new(start: Timestamp) -> Window {
+    Window {
+      start,
+      age: Aggregate::default(),
+      period: Aggregate::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Aggregate {
+  count: u64,
+  sum: f64,
+  sum_of_squares: f64,
+  min: f64,
+  max: f64,
+}
+
+impl Aggregate {
+  fn add(&mut self, x: f64) {
+    if self.count == 0 {
+      self.min = x;
+      self.max = x;
+    } else {
+      self.min = self.min.min(x);
+      self.max = self.max.max(x);
+    }
+    self.count += 1;
+    self.sum += x;
+    self.sum_of_squares += x * x;
+  }
+
+  fn mean(&self) -> f64 {
+    self.sum / self.count as f64
+  }
+
+  fn stddev(&self) -> f64 {
+    let mean = self.mean();
+    (self.sum_of_squares / self.count as f64 - mean * mean)
+      .max(0.0)
+      .sqrt()
+  }
+
+  // As in ROS 2 topic statistics: NaN, if there are no samples.
+  fn data_points(&self) -> Vec<StatisticDataPoint> {
+    let (average, minimum, maximum, stddev) = if self.count > 0 {
+      (self.mean(), self.min, self.max, self.stddev())
+    } else {
+      (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+    };
+    [
+      (StatisticDataType::AVERAGE, average),
+      (StatisticDataType::MINIMUM, minimum),
+      (StatisticDataType::MAXIMUM, maximum),
+      (StatisticDataType::STDDEV, stddev),
+      (StatisticDataType::SAMPLE_COUNT, self.count as f64),
+    ]
+    .iter()
+    .map(|&(data_type, data)| StatisticDataPoint { data_type, data })
+    .collect()
+  }
+}
+
+fn millis(d: rustdds::Duration) -> f64 {
+  d.to_nanoseconds() as f64 / 1_000_000.0
+}
+
+fn to_time(t: Timestamp) -> builtin_interfaces::Time {
+  builtin_interfaces::Time::from_nanos((t - Timestamp::ZERO).to_nanoseconds())
+}
+
+// ----------------------------------------------------
+// ----------------------------------------------------
+
+#[test]
+fn test_sequence_gaps_and_latency() {
+  let mut tracker = PublisherTracker::new(Timestamp::ZERO);
+  let at_millis =
+    |ms: i64| Timestamp::ZERO + rustdds::Duration::from_frac_seconds(ms as f64 / 1000.0);
+
+  // 3 and 4 are skipped, then 4 arrives late.
+  for (i, sn) in [1, 2, 5, 4, 6].iter().enumerate() {
+    tracker.record(*sn, at_millis(100 * i as i64), Some(i as f64));
+  }
+  let statistics = tracker.statistics(Gid::from(rustdds::GUID::from_bytes([1; 16])));
+  assert_eq!(statistics.received, 5);
+  assert_eq!(statistics.lost, 1);
+  assert_eq!(statistics.out_of_order, 1);
+  let latency = statistics.latency.unwrap();
+  assert_eq!((latency.min, latency.mean, latency.max), (0.0, 2.0, 4.0));
+  assert_eq!((latency.p50, latency.p99), (2.0, 4.0));
+  let rate = statistics.rate.unwrap();
+  assert!((rate - 10.0).abs() < 0.01, "rate = {}", rate);
+
+  // Duplicates do not cancel the loss of 3.
+  tracker.record(4, at_millis(500), None);
+  tracker.record(6, at_millis(600), None);
+  let statistics = tracker.statistics(Gid::from(rustdds::GUID::from_bytes([1; 16])));
+  assert_eq!(statistics.received, 7);
+  assert_eq!(statistics.lost, 1);
+  assert_eq!(statistics.out_of_order, 1);
+  assert_eq!(statistics.duplicates, 2);
+  tracker.record(3, at_millis(700), None);
+  let statistics = tracker.statistics(Gid::from(rustdds::GUID::from_bytes([1; 16])));
+  assert_eq!((statistics.lost, statistics.out_of_order), (0, 2));
+
+  // A huge gap is counted, but only the latest skipped ones are remembered.
+  tracker.record(1_000_000, at_millis(800), None);
+  assert_eq!(tracker.missing.len(), MISSING_SAMPLES);
+  tracker.record(10, at_millis(900), None);
+  tracker.record(999_999, at_millis(1000), None);
+  let statistics = tracker.statistics(Gid::from(rustdds::GUID::from_bytes([1; 16])));
+  assert_eq!(statistics.lost, 1_000_000 - 6 - 1 - 1);
+  assert_eq!((statistics.out_of_order, statistics.duplicates), (3, 3));
+
+  let mut aggregate = Aggregate::default();
+  assert!(aggregate.data_points()[0].data.is_nan());
+  aggregate.add(1.0);
+  aggregate.add(3.0);
+  let data: Vec<f64> = aggregate.data_points().iter().map(|p| p.data).collect();
+  assert_eq!(data, vec![2.0, 1.0, 3.0, 1.0, 2.0]);
+}
@@ -0,0 +1,39 @@
+//! Corresponds to package [statistics_msgs](https://index.ros.org/p/statistics_msgs/).
+//! These are published e.g. by ROS 2 topic statistics.
+//!
+//! See [`TopicStatistics`](crate::statistics::TopicStatistics).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{builtin_interfaces::Time, message::Message};
+
+/// Constants for [`StatisticDataPoint::data_type`]
+pub struct StatisticDataType;
+
+impl StatisticDataType {
+  pub const UNINITIALIZED: u8 = 0;
+  pub const AVERAGE: u8 = 1;
+  pub const MINIMUM: u8 = 2;
+  pub const MAXIMUM: u8 = 3;
+  pub const STDDEV: u8 = 4;
+  pub const SAMPLE_COUNT: u8 = 5;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatisticDataPoint {
+  pub data_type: u8, // one of StatisticDataType
+  pub data: f64,
+}
+impl Message for StatisticDataPoint {}
+
+/// Statistics of one metric over a time window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsMessage {
+  pub measurement_source_name: String, // e.g. Node name
+  pub metrics_source: String,          // e.g. "message_age"
+  pub unit: String,
+  pub window_start: Time,
+  pub window_stop: Time,
+  pub statistics: Vec<StatisticDataPoint>,
+}
+impl Message for MetricsMessage {}